@@ -95,6 +95,7 @@ pub fn widget(args: TokenStream, input: TokenStream) -> TokenStream {
             for s in self.imp().selectors.take() {
                 #store().deselect(s)
             }
+            crate::gtk_rust_app::store::unregister_widget(self.upcast_ref());
         }
     } else {
         quote! {}
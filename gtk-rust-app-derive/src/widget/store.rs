@@ -54,6 +54,15 @@ pub fn get_selector_bindings(fields: &Punctuated<Field, Comma>) -> TokenStream {
             let attr_tokens = &attr.tokens;
             let args: Args = parse2(attr_tokens.clone()).unwrap();
             let paths = args.paths;
+            let path_names: Vec<String> = paths
+                .iter()
+                .map(|p| {
+                    p.iter()
+                        .map(|i| i.to_string())
+                        .collect::<Vec<String>>()
+                        .join(".")
+                })
+                .collect();
             let sd = quote!(
                 {
                     #[allow(clippy::redundant_closure_call)]
@@ -68,11 +77,19 @@ pub fn get_selector_bindings(fields: &Punctuated<Field, Comma>) -> TokenStream {
                             state
                         },
                         glib::clone!(@weak obj => move |state| {
-                        <Self as ObjectSubclass>::Type::#name(&obj, state);
+                        crate::gtk_rust_app::store::profile_selector(obj.upcast_ref(), stringify!(#name), || {
+                            <Self as ObjectSubclass>::Type::#name(&obj, state);
+                        });
                     }));
                     let mut selectors = self.selectors.take();
                     selectors.push(selector_id);
                     self.selectors.set(selectors);
+                    crate::gtk_rust_app::store::register_selector(
+                        obj.upcast_ref(),
+                        <Self as ObjectSubclass>::NAME,
+                        stringify!(#name),
+                        &[#(#path_names),*],
+                    );
                 }
             );
             // println!("{}", sd);
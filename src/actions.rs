@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Keys of the App.toml `[actions]` section which are read by gtk-rust-app and not by cargo-gra.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// The gtk-rust-app specific keys of an action. All other keys are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ActionExtras {
+    pub menu: Option<String>,
+    pub menu_label: Option<String>,
+    pub menu_section: Option<String>,
+    pub undoable: Option<bool>,
+    /// The position of the action in the `[actions]` section.
+    #[serde(skip)]
+    pub position: usize,
+}
+
+/// Parse the gtk-rust-app specific action keys from the App.toml.
+pub(crate) fn parse_action_extras(
+    app_toml: &[u8],
+) -> Result<HashMap<String, ActionExtras>, String> {
+    let app_toml: toml::Value = toml::from_slice(app_toml).map_err(|e| e.to_string())?;
+    let actions = match app_toml.get("actions") {
+        Some(toml::Value::Table(actions)) => actions,
+        Some(_) => return Err("[actions] is not a table".into()),
+        None => return Ok(HashMap::new()),
+    };
+    // The table keeps the manifest order (`preserve_order` feature of toml).
+    actions
+        .iter()
+        .enumerate()
+        .map(|(position, (name, value))| {
+            let mut extras: ActionExtras = value
+                .clone()
+                .try_into()
+                .map_err(|e| format!("Invalid action '{}': {}", name, e))?;
+            extras.position = position;
+            Ok((name.clone(), extras))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_action_extras_reads_kebab_case_keys() {
+        let extras = parse_action_extras(
+            br#"
+            [app]
+            id = "org.example.App"
+
+            [actions]
+            refresh = { accelerators = ["<primary>R"], menu = "primary", menu-label = "Refresh", menu-section = "data" }
+            remove-todo = { type = "s", undoable = true }
+            "#,
+        )
+        .unwrap();
+
+        let refresh = &extras["refresh"];
+        assert_eq!(refresh.menu.as_deref(), Some("primary"));
+        assert_eq!(refresh.menu_label.as_deref(), Some("Refresh"));
+        assert_eq!(refresh.menu_section.as_deref(), Some("data"));
+        assert_eq!(refresh.undoable, None);
+
+        let remove = &extras["remove-todo"];
+        assert_eq!(remove.menu, None);
+        assert_eq!(remove.undoable, Some(true));
+    }
+
+    #[test]
+    fn parse_action_extras_without_actions_section() {
+        let extras = parse_action_extras(b"[app]\nid = \"org.example.App\"\n").unwrap();
+        assert!(extras.is_empty());
+    }
+
+    #[test]
+    fn parse_action_extras_keeps_the_manifest_order() {
+        let extras = parse_action_extras(
+            b"[actions]\nzoom = {}\nabout = {}\n[actions.middle]\nmenu = \"primary\"\n",
+        )
+        .unwrap();
+        assert_eq!(extras["zoom"].position, 0);
+        assert_eq!(extras["about"].position, 1);
+        assert_eq!(extras["middle"].position, 2);
+    }
+
+    #[test]
+    fn parse_action_extras_rejects_invalid_values() {
+        assert!(parse_action_extras(b"actions = 1").is_err());
+        assert!(parse_action_extras(b"[actions]\nundo-me = { undoable = \"yes\" }").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Keep the last gstore actions and write them to a crash report on panic.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::store::RecordedAction;
+
+/// The number of bytes of the `Debug` output of the state which are kept per action.
+const STATE_SUMMARY_LEN: usize = 512;
+
+/// A reduced action in the crash report.
+#[derive(Debug, Clone, Serialize)]
+struct Breadcrumb {
+    /// ISO 8601 timestamp (UTC).
+    timestamp: String,
+    action: RecordedAction,
+    /// The beginning of the state after the action was reduced.
+    state_summary: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CrashReport<'a> {
+    app_id: &'a str,
+    timestamp: String,
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    actions: Vec<Breadcrumb>,
+}
+
+/// The breadcrumbs are written by the main thread and read by the panic hook of any thread.
+static BREADCRUMBS: Lazy<Mutex<VecDeque<Breadcrumb>>> = Lazy::new(Default::default);
+
+struct BreadcrumbsMiddleware {
+    capacity: usize,
+}
+
+impl<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static> gstore::Middleware<S>
+    for BreadcrumbsMiddleware
+{
+    fn post_reduce(&self, a: &gstore::Action, s: &S) {
+        if a.name() == crate::store::REPLACE_STATE_ACTION {
+            return;
+        }
+        let breadcrumb = Breadcrumb {
+            timestamp: now_iso8601(),
+            action: RecordedAction::from_action(a),
+            state_summary: summary(s),
+        };
+        // Do not panic in a middleware if another thread panicked while holding the lock.
+        let mut breadcrumbs = match BREADCRUMBS.lock() {
+            Ok(b) => b,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        breadcrumbs.push_back(breadcrumb);
+        while breadcrumbs.len() > self.capacity {
+            breadcrumbs.pop_front();
+        }
+    }
+}
+
+/// Keep the last `capacity` actions of the store and write them to
+/// `$XDG_STATE_HOME/<app-id>/crash-<timestamp>.json` on panic.
+pub(crate) fn add_crash_breadcrumbs<
+    S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static,
+>(
+    app_id: &str,
+    store: &mut gstore::Store<S>,
+    capacity: usize,
+) {
+    store.append_middleware(Box::new(BreadcrumbsMiddleware { capacity }));
+
+    let app_id = app_id.to_string();
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_crash_report(&app_id, info) {
+            Ok(path) => eprintln!("Wrote crash report to {:?}", path),
+            Err(e) => eprintln!("Could not write crash report: {}", e),
+        }
+        previous_hook(info);
+    }));
+}
+
+/// Formats at most `STATE_SUMMARY_LEN` bytes. Larger values are not formatted completely.
+struct SummaryWriter(String);
+
+impl Write for SummaryWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let remaining = STATE_SUMMARY_LEN - self.0.len();
+        if s.len() <= remaining {
+            self.0.push_str(s);
+            return Ok(());
+        }
+        let mut end = remaining;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.0.push_str(&s[..end]);
+        // Abort the formatting of the rest of the value.
+        Err(std::fmt::Error)
+    }
+}
+
+fn summary(state: &impl std::fmt::Debug) -> String {
+    let mut w = SummaryWriter(String::new());
+    if write!(w, "{:?}", state).is_err() {
+        w.0.push('…');
+    }
+    w.0
+}
+
+fn write_crash_report(app_id: &str, info: &std::panic::PanicInfo) -> std::io::Result<PathBuf> {
+    let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Unknown panic".to_string()
+    };
+
+    // The panicking thread may hold the lock. Use what is there.
+    let actions = match BREADCRUMBS.try_lock() {
+        Ok(b) => b.iter().cloned().collect(),
+        Err(std::sync::TryLockError::Poisoned(p)) => p.into_inner().iter().cloned().collect(),
+        Err(std::sync::TryLockError::WouldBlock) => Vec::new(),
+    };
+
+    let report = CrashReport {
+        app_id,
+        timestamp: now_iso8601(),
+        message,
+        location: info.location().map(|l| l.to_string()),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        actions,
+    };
+
+    let dir = state_dir().join(app_id);
+    std::fs::create_dir_all(&dir)?;
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = dir.join(format!("crash-{}.json", ts));
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// `$XDG_STATE_HOME` or `~/.local/state`.
+fn state_dir() -> PathBuf {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| glib::home_dir().join(".local").join("state"))
+}
+
+fn now_iso8601() -> String {
+    glib::DateTime::now_utc()
+        .ok()
+        .and_then(|d| d.format_iso8601().ok())
+        .map(|d| d.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_summaries_are_complete() {
+        assert_eq!(summary(&Some(42)), "Some(42)");
+    }
+
+    #[test]
+    fn long_summaries_are_truncated() {
+        let s = summary(&vec![0u8; 1000]);
+        assert!(s.ends_with('…'));
+        assert_eq!(s.trim_end_matches('…').len(), STATE_SUMMARY_LEN);
+    }
+
+    #[test]
+    fn exactly_fitting_summaries_are_complete() {
+        let mut w = SummaryWriter(String::new());
+        assert!(w.write_str(&"a".repeat(STATE_SUMMARY_LEN)).is_ok());
+        assert!(w.write_str("").is_ok());
+        assert!(w.write_str("a").is_err());
+        assert_eq!(w.0.len(), STATE_SUMMARY_LEN);
+    }
+
+    #[test]
+    fn truncation_keeps_char_boundaries() {
+        // The opening quote shifts the two byte chars, so the limit falls into the middle of one.
+        let s = summary(&"ä".repeat(STATE_SUMMARY_LEN));
+        let truncated = s.trim_end_matches('…');
+        assert_eq!(truncated.len(), STATE_SUMMARY_LEN - 1);
+        assert!(truncated.starts_with('"'));
+        assert!(truncated[1..].chars().all(|c| c == 'ä'));
+    }
+}
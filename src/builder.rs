@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::HashMap;
+
 use gdk4::gio::SimpleAction;
 use gdk4::prelude::{ApplicationExt, ApplicationExtManual};
 use glib::VariantTy;
@@ -8,6 +10,7 @@ use gtk::builders::ApplicationBuilder;
 use gtk::prelude::GtkApplicationExt;
 use gtk::prelude::*;
 
+use crate::actions::{parse_action_extras, ActionExtras};
 use crate::{init_gettext, load_resources};
 
 /// Load the given css styles for your app.
@@ -32,14 +35,67 @@ pub struct AppBuilder {
 
     #[cfg(feature = "store")]
     delegate_store: Option<glib::Sender<(SimpleAction, Option<glib::Variant>)>>,
+    #[cfg(feature = "store")]
+    gstore_debug: GstoreDebugOptions,
+    #[cfg(feature = "store")]
+    gstore_setup: Option<GstoreSetup>,
+}
+
+/// Options of the gstore debugging tools.
+#[cfg(feature = "store")]
+#[derive(Debug, Clone)]
+pub struct GstoreDebugOptions {
+    /// Enable the debug window in release builds if the app is started with `GRA_DEBUG=1`.
+    pub in_release: bool,
+    /// The accelerator which opens the debug window.
+    pub accelerator: String,
+    /// The number of (action, state) snapshots the debug window keeps.
+    pub max_history: usize,
+    /// Additionally serve the debug events as JSON lines on a local socket.
+    #[cfg(unix)]
+    pub remote: Option<crate::store::DebugAddress>,
+    /// Keep this number of actions and write them to a crash report on panic.
+    /// This is independent of the debug window and also works in release builds.
+    pub crash_breadcrumbs: Option<usize>,
+}
+
+#[cfg(feature = "store")]
+impl Default for GstoreDebugOptions {
+    fn default() -> Self {
+        Self {
+            in_release: false,
+            accelerator: "<primary><alt>G".into(),
+            max_history: 100,
+            #[cfg(unix)]
+            remote: None,
+            crash_breadcrumbs: None,
+        }
+    }
+}
+
+#[cfg(feature = "store")]
+impl GstoreDebugOptions {
+    /// The debug window is always enabled in debug builds.
+    pub fn enabled(&self) -> bool {
+        cfg!(debug_assertions)
+            || (self.in_release && std::env::var("GRA_DEBUG").map_or(false, |v| v == "1"))
+    }
 }
 
+#[cfg(feature = "store")]
+type GstoreSetup = Box<dyn FnOnce(&gtk::Application, &ProjectDescriptor, &GstoreDebugOptions)>;
+
 impl AppBuilder {
     pub fn build(
         self,
         startup: impl Fn(&gtk::Application, &ProjectDescriptor, Option<&gdk4::gio::Settings>) + 'static,
         activate: impl Fn(&gtk::Application, &ProjectDescriptor, Option<&gdk4::gio::Settings>) + 'static,
     ) {
+        #[cfg(feature = "store")]
+        if let Some(setup) = self.gstore_setup {
+            setup(&self.app, &self.project_descriptor, &self.gstore_debug);
+        }
+
         let project_descriptor = self.project_descriptor;
         let settings = self.settings;
         let app = self.app;
@@ -103,12 +159,75 @@ impl AppBuilder {
     }
 
     #[cfg(feature = "store")]
-    pub fn store<S: std::fmt::Debug + Clone + Default + PartialEq + Eq>(
+    pub fn store<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static>(
         mut self,
         store: &'static mut gstore::Store<S>,
     ) -> Self {
-        add_gstore_debug_action(&self.app, store);
         self.delegate_store = Some(store.delegate());
+        self.gstore_setup = Some(Box::new(move |app, project_descriptor, options| {
+            add_gstore_middlewares(app, project_descriptor, store, None, options)
+        }));
+        self
+    }
+
+    /// Like `store` but the gstore debug window additionally shows the state as JSON tree
+    /// and the paths each action changed.
+    #[cfg(feature = "store")]
+    pub fn serializable_store<
+        S: std::fmt::Debug + Clone + Default + PartialEq + Eq + serde::Serialize + 'static,
+    >(
+        mut self,
+        store: &'static mut gstore::Store<S>,
+    ) -> Self {
+        self.delegate_store = Some(store.delegate());
+        self.gstore_setup = Some(Box::new(move |app, project_descriptor, options| {
+            add_gstore_middlewares(
+                app,
+                project_descriptor,
+                store,
+                Some(serialize_state::<S>),
+                options,
+            )
+        }));
+        self
+    }
+
+    /// Allow to open the gstore debug window in release builds if the app is started with
+    /// `GRA_DEBUG=1`. In debug builds the debug window is always available.
+    #[cfg(feature = "store")]
+    pub fn gstore_debug_in_release(mut self) -> Self {
+        self.gstore_debug.in_release = true;
+        self
+    }
+
+    /// Set the accelerator which opens the gstore debug window. Default: `<primary><alt>G`.
+    #[cfg(feature = "store")]
+    pub fn gstore_debug_accelerator(mut self, accelerator: &str) -> Self {
+        self.gstore_debug.accelerator = accelerator.into();
+        self
+    }
+
+    /// Serve the gstore debug events as JSON lines on a Unix socket or a localhost TCP port.
+    /// Another app instance can show them with `store::attach_debug_window`.
+    #[cfg(all(feature = "store", unix))]
+    pub fn gstore_debug_remote(mut self, address: crate::store::DebugAddress) -> Self {
+        self.gstore_debug.remote = Some(address);
+        self
+    }
+
+    /// Keep the last `capacity` actions with their state and write them together with the panic
+    /// message and backtrace to `$XDG_STATE_HOME/<app-id>/crash-<timestamp>.json` if the app
+    /// panics. Users can attach this file to bug reports.
+    #[cfg(feature = "store")]
+    pub fn crash_breadcrumbs(mut self, capacity: usize) -> Self {
+        self.gstore_debug.crash_breadcrumbs = Some(capacity);
+        self
+    }
+
+    /// Set the number of (action, state) snapshots the gstore debug window keeps. Default: 100.
+    #[cfg(feature = "store")]
+    pub fn gstore_debug_history_size(mut self, max_history: usize) -> Self {
+        self.gstore_debug.max_history = max_history;
         self
     }
 
@@ -116,30 +235,202 @@ impl AppBuilder {
         self.styles = Some(styles);
         self
     }
+
+    /// Accept URIs from the command line and D-Bus `Open` calls and delegate each of them
+    /// to the `app.open-uri` action (see `LeafletLayout::handle_uris`).
+    /// URIs with other schemes than the given one (e.g. `myapp`) are ignored.
+    pub fn handle_uris(self, scheme: &str) -> Self {
+        self.app
+            .set_flags(self.app.flags() | gdk4::gio::ApplicationFlags::HANDLES_OPEN);
+        let scheme = scheme.to_string();
+        self.app.connect_open(move |app, files, _hint| {
+            // Only build the UI if the app was not running yet.
+            if app.active_window().is_none() {
+                app.activate();
+            }
+            for file in files {
+                if !file.has_uri_scheme(&scheme) {
+                    warn!("Ignoring URI '{}' with a foreign scheme", file.uri());
+                    continue;
+                }
+                app.activate_action("open-uri", Some(&file.uri().as_str().to_variant()));
+            }
+        });
+        self
+    }
+}
+
+/// Build the primary menu from all actions with `menu = "primary"` in the App.toml.
+///
+/// Items are grouped by their `menu-section`. The `preferences`, `shortcuts` and `about`
+/// actions are placed in the last section and get default labels.
+pub fn primary_menu(project_descriptor: &ProjectDescriptor) -> gdk4::gio::Menu {
+    let extras = action_extras();
+    let actions = project_descriptor
+        .actions
+        .iter()
+        .flatten()
+        .filter_map(|(name, _)| extras.get(name).map(|desc| (name, desc)));
+
+    let menu = gdk4::gio::Menu::new();
+    for items in primary_menu_sections(actions) {
+        let section = gdk4::gio::Menu::new();
+        for (action_name, label) in items {
+            section.append(Some(&label), Some(&format!("app.{}", action_name)));
+        }
+        menu.append_section(None, &section);
+    }
+    menu
+}
+
+/// The action names and labels of the primary menu grouped by section.
+///
+/// Sections and their items follow the order of the actions in the App.toml. A section is
+/// placed where its first action is declared. Actions without `menu-section` form one section
+/// as well. The builtin actions come last.
+fn primary_menu_sections<'a>(
+    actions: impl Iterator<Item = (&'a String, &'a ActionExtras)>,
+) -> Vec<Vec<(String, String)>> {
+    use gettextrs::gettext;
+
+    const BUILTIN_ACTIONS: [&str; 3] = ["preferences", "shortcuts", "about"];
+
+    let mut sections: Vec<(Option<String>, Vec<(String, String)>)> = Vec::new();
+    let mut builtin = Vec::new();
+
+    let mut actions: Vec<_> = actions
+        .filter(|(_, desc)| desc.menu.as_deref() == Some("primary"))
+        .collect();
+    actions.sort_by_key(|(name, desc)| (desc.position, name.as_str()));
+
+    for (action_name, desc) in actions {
+        let label = desc.menu_label.as_ref().map(|l| gettext(l));
+        match action_name.as_str() {
+            "preferences" if desc.menu_section.is_none() => builtin.push((
+                action_name.clone(),
+                label.unwrap_or_else(|| gettext("Preferences")),
+            )),
+            "shortcuts" if desc.menu_section.is_none() => builtin.push((
+                action_name.clone(),
+                label.unwrap_or_else(|| gettext("Keyboard Shortcuts")),
+            )),
+            "about" if desc.menu_section.is_none() => builtin.push((
+                action_name.clone(),
+                label.unwrap_or_else(|| gettext("About")),
+            )),
+            _ => {
+                let item = (
+                    action_name.clone(),
+                    label.unwrap_or_else(|| action_name.clone()),
+                );
+                match sections.iter_mut().find(|(s, _)| *s == desc.menu_section) {
+                    Some((_, items)) => items.push(item),
+                    None => sections.push((desc.menu_section.clone(), vec![item])),
+                }
+            }
+        }
+    }
+    builtin.sort_by_key(|(name, _)| BUILTIN_ACTIONS.iter().position(|b| b == name));
+
+    sections
+        .into_iter()
+        .map(|(_, items)| items)
+        .chain(std::iter::once(builtin))
+        .filter(|items| !items.is_empty())
+        .collect()
+}
+
+/// The gtk-rust-app specific action keys of the App.toml. Set by `builder`.
+static ACTION_EXTRAS: once_cell::sync::OnceCell<HashMap<String, ActionExtras>> =
+    once_cell::sync::OnceCell::new();
+
+/// The gtk-rust-app specific keys of the App.toml actions by action name.
+pub(crate) fn action_extras() -> &'static HashMap<String, ActionExtras> {
+    ACTION_EXTRAS.get_or_init(Default::default)
 }
 
 #[cfg(feature = "store")]
-static DEBUG_SENDER: once_cell::sync::OnceCell<glib::Sender<(gstore::Action, String)>> =
+static DEBUG_SENDER: once_cell::sync::OnceCell<glib::Sender<crate::ui::debugging::DebugEvent>> =
     once_cell::sync::OnceCell::new();
 
-#[cfg(not(debug_assertions))]
-fn add_gstore_debug_action<S: std::fmt::Debug + Clone + Default + PartialEq + Eq>(
+#[cfg(feature = "store")]
+type StateSerializer<S> = fn(&S) -> Option<serde_json::Value>;
+
+#[cfg(feature = "store")]
+fn serialize_state<S: serde::Serialize>(state: &S) -> Option<serde_json::Value> {
+    serde_json::to_value(state)
+        .map_err(|e| warn!("Could not serialize gstore state: {}", e))
+        .ok()
+}
+
+#[cfg(feature = "store")]
+fn add_gstore_middlewares<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static>(
     application: &gtk::Application,
+    project_descriptor: &ProjectDescriptor,
     store: &mut gstore::Store<S>,
+    serialize: Option<StateSerializer<S>>,
+    options: &GstoreDebugOptions,
 ) {
+    crate::undo::add_undo(application, project_descriptor, action_extras(), store);
+    if let Some(capacity) = options.crash_breadcrumbs {
+        crate::breadcrumbs::add_crash_breadcrumbs(&project_descriptor.app.id, store, capacity);
+    }
+    if options.enabled() {
+        add_gstore_debug_action(application, project_descriptor, store, serialize, options);
+    }
 }
 
-#[cfg(debug_assertions)]
 #[cfg(feature = "store")]
-fn add_gstore_debug_action<S: std::fmt::Debug + Clone + Default + PartialEq + Eq>(
+fn add_gstore_debug_action<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static>(
     application: &gtk::Application,
+    project_descriptor: &ProjectDescriptor,
     store: &mut gstore::Store<S>,
+    serialize: Option<StateSerializer<S>>,
+    options: &GstoreDebugOptions,
 ) {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+    #[cfg(unix)]
+    use crate::ui::debugging::DebugServer;
+    use crate::ui::debugging::{ActionMetrics, DebugEvent};
     use glib::PRIORITY_DEFAULT;
     use gstore::Middleware;
 
-    struct GstoreDebuggingMiddleware;
-    let m = GstoreDebuggingMiddleware;
+    type History<S> = Rc<RefCell<VecDeque<(usize, S)>>>;
+
+    struct GstoreDebuggingMiddleware<S> {
+        history: History<S>,
+        max_history: usize,
+        next_index: std::cell::Cell<usize>,
+        #[cfg(unix)]
+        remote: Option<Rc<DebugServer>>,
+        serialize: Option<StateSerializer<S>>,
+        /// The serialized state before the action which is reduced.
+        before_json: RefCell<Option<serde_json::Value>>,
+    }
+
+    let history: History<S> = Default::default();
+    let m = GstoreDebuggingMiddleware {
+        history: history.clone(),
+        max_history: options.max_history,
+        next_index: Default::default(),
+        #[cfg(unix)]
+        remote: options.remote.as_ref().and_then(|address| {
+            DebugServer::start(address)
+                .map_err(|e| {
+                    error!(
+                        "Could not serve gstore debug events on {:?}: {}",
+                        address, e
+                    )
+                })
+                .ok()
+                .map(Rc::new)
+        }),
+        serialize,
+        before_json: Default::default(),
+    };
+
+    crate::store::enable_inspector();
 
     let (send, receiver) = glib::MainContext::channel(PRIORITY_DEFAULT);
     if DEBUG_SENDER.set(send).is_err() {
@@ -147,38 +438,135 @@ fn add_gstore_debug_action<S: std::fmt::Debug + Clone + Default + PartialEq + Eq
     }
 
     impl<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static> Middleware<S>
-        for GstoreDebuggingMiddleware
+        for GstoreDebuggingMiddleware<S>
     {
+        fn pre_reduce(&self, a: &gstore::Action, s: &S) {
+            if a.name() == crate::store::REPLACE_STATE_ACTION {
+                crate::store::profile_action(None);
+                return;
+            }
+            crate::store::profile_action(Some(self.next_index.get()));
+            crate::store::take_reducer_duration();
+            // The state may have been replaced since the last action (undo, time travel, replay).
+            self.before_json
+                .replace(self.serialize.and_then(|serialize| serialize(s)));
+        }
+
         fn post_reduce(&self, a: &gstore::Action, s: &S) {
-            if let Some(sender) = DEBUG_SENDER.get() {
-                if let Err(e) = sender.send((a.clone(), format!("{:#?}", s))) {
-                    println!("Failed to delegate action to gstore debugging: {}", e)
-                }
-            } else {
-                println!("Failed to send action to gstore debugging UI.")
+            if a.name() == crate::store::REPLACE_STATE_ACTION {
+                return;
+            }
+            // Measured by `replaceable_reducer` to not include the time of other middlewares.
+            let reducer_duration = crate::store::take_reducer_duration().unwrap_or_default();
+            let index = self.next_index.get();
+            self.next_index.set(index + 1);
+
+            let mut history = self.history.borrow_mut();
+            history.push_back((index, s.clone()));
+            while history.len() > self.max_history {
+                history.pop_front();
             }
+
+            let state_json = self.serialize.and_then(|serialize| serialize(s));
+            let changes = match (self.before_json.take(), &state_json) {
+                (Some(before), Some(after)) => crate::ui::debugging::diff(&before, after),
+                _ => Vec::new(),
+            };
+
+            let mut event = DebugEvent {
+                index,
+                action: a.clone(),
+                state: format!("{:#?}", s),
+                state_json,
+                changes,
+                metrics: ActionMetrics {
+                    reducer_duration,
+                    ..Default::default()
+                },
+            };
+
+            // The selector callbacks are called after the middlewares. Send the event once
+            // they are done to include their timing.
+            #[cfg(unix)]
+            let remote = self.remote.clone();
+            glib::idle_add_local_once(move || {
+                let profile = crate::store::take_selector_profile(index);
+                event.metrics.selector_callbacks = profile.callbacks;
+                event.metrics.selector_duration = profile.duration;
+                #[cfg(unix)]
+                if let Some(remote) = remote {
+                    remote.send(&event);
+                }
+                if let Some(sender) = DEBUG_SENDER.get() {
+                    if let Err(e) = sender.send(event) {
+                        println!("Failed to delegate action to gstore debugging: {}", e)
+                    }
+                } else {
+                    println!("Failed to send action to gstore debugging UI.")
+                }
+            });
         }
     }
 
     let name = &"gstore-debug";
     let action = gdk4::gio::SimpleAction::new(name, None);
-    let w = create_debug_window(receiver);
+    let w = create_debug_view(receiver);
+    w.set_max_history(options.max_history);
+
+    // Jump to a snapshot of the history (`Some(index)`) or resume to the latest state (`None`).
+    let delegate = store.delegate();
+    w.set_time_travel_handler(move |index| {
+        let history = history.borrow();
+        let snapshot = match index {
+            Some(index) => history.iter().find(|(i, _)| *i == index),
+            None => history.back(),
+        };
+        if let Some((_, state)) = snapshot {
+            crate::store::replace_state(&delegate, state.clone());
+        }
+    });
+
+    // Reset to the default state and dispatch the actions of an imported session.
+    let delegate = store.delegate();
+    w.set_replay_handler(move |session| crate::store::replay_into_store::<S>(&delegate, session));
+
+    // Dispatch any action of the manifest from the debug window.
+    if let Some(actions) = &project_descriptor.actions {
+        w.set_dispatchable_actions(
+            actions
+                .iter()
+                .map(|(name, desc)| (name.clone(), desc.type_.clone()))
+                .collect(),
+        );
+    }
+    let delegate = store.delegate();
+    w.set_dispatch_handler(move |name, argument| crate::store::dispatch(&delegate, name, argument));
+
     store.append_middleware(Box::new(m));
-    action.connect_activate(glib::clone!(@weak application, @weak w => move |_, _| {
-        w.show();
-    }));
-    application.set_accels_for_action(&format!("app.{}", name), &["<primary><alt>G"]);
+    let window = create_debug_window(&w);
+    action.connect_activate(
+        glib::clone!(@weak application, @weak window => move |_, _| {
+            window.show();
+        }),
+    );
+    application.set_accels_for_action(&format!("app.{}", name), &[&options.accelerator]);
     application.add_action(&action);
 }
 
-#[cfg(debug_assertions)]
-fn create_debug_window(recv: glib::Receiver<(gstore::Action, String)>) -> libadwaita::Window {
-    let d = crate::ui::debugging::GstoreDebug::new(Some(recv));
+#[cfg(feature = "store")]
+pub(crate) fn create_debug_view(
+    recv: glib::Receiver<crate::ui::debugging::DebugEvent>,
+) -> crate::ui::debugging::GstoreDebug {
+    crate::ui::debugging::GstoreDebug::new(Some(recv))
+}
+
+#[cfg(feature = "store")]
+pub(crate) fn create_debug_window(d: &crate::ui::debugging::GstoreDebug) -> libadwaita::Window {
     libadwaita::Window::builder()
         .default_height(600)
         .default_width(500)
         .hide_on_close(true)
-        .content(&d)
+        .content(d)
         .build()
 }
 
@@ -261,6 +649,14 @@ pub fn builder(
         );
     }
     let project_descriptor = project_descriptor.unwrap();
+    match parse_action_extras(app_toml) {
+        Ok(extras) => {
+            if ACTION_EXTRAS.set(extras).is_err() {
+                warn!("The App.toml actions were already parsed by another builder.");
+            }
+        }
+        Err(e) => panic!("Could not parse App.toml: {}", e),
+    }
 
     let app_desc = &project_descriptor.app;
 
@@ -284,5 +680,80 @@ pub fn builder(
         styles: None,
         #[cfg(feature = "store")]
         delegate_store: Default::default(),
+        #[cfg(feature = "store")]
+        gstore_debug: Default::default(),
+        #[cfg(feature = "store")]
+        gstore_setup: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections(app_toml: &str) -> Vec<Vec<(String, String)>> {
+        let extras = parse_action_extras(app_toml.as_bytes()).unwrap();
+        primary_menu_sections(extras.iter())
+    }
+
+    fn item(name: &str, label: &str) -> (String, String) {
+        (name.to_string(), label.to_string())
+    }
+
+    #[test]
+    fn primary_menu_groups_sections_in_manifest_order() {
+        let sections = sections(
+            r#"
+            [actions]
+            export = { menu = "primary", menu-section = "file" }
+            refresh = { menu = "primary", menu-label = "Refresh", menu-section = "data" }
+            import = { menu = "primary", menu-section = "file" }
+            zoom = { menu = "primary" }
+            hidden = { accelerators = ["<primary>H"] }
+            "#,
+        );
+        assert_eq!(
+            sections,
+            vec![
+                vec![item("export", "export"), item("import", "import")],
+                vec![item("refresh", "Refresh")],
+                vec![item("zoom", "zoom")],
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_menu_places_builtin_actions_last_with_default_labels() {
+        let sections = sections(
+            r#"
+            [actions]
+            about = { menu = "primary" }
+            shortcuts = { menu = "primary" }
+            preferences = { menu = "primary", menu-label = "Settings" }
+            sync = { menu = "primary" }
+            "#,
+        );
+        assert_eq!(
+            sections,
+            vec![
+                vec![item("sync", "sync")],
+                vec![
+                    item("preferences", "Settings"),
+                    item("shortcuts", "Keyboard Shortcuts"),
+                    item("about", "About"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_menu_keeps_builtin_actions_with_a_section_in_that_section() {
+        let sections = sections(
+            r#"
+            [actions]
+            about = { menu = "primary", menu-section = "help" }
+            "#,
+        );
+        assert_eq!(sections, vec![vec![item("about", "about")]]);
     }
 }
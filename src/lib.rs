@@ -4,15 +4,26 @@
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "ui")]
+mod actions;
 #[cfg(feature = "ui")]
 pub mod builder;
 
 #[cfg(feature = "ui")]
 mod ui;
 
+#[cfg(all(feature = "ui", feature = "store"))]
+mod breadcrumbs;
+#[cfg(all(feature = "ui", feature = "store"))]
+pub mod store;
+#[cfg(all(feature = "ui", feature = "store"))]
+mod undo;
+
 #[cfg(feature = "ui")]
 pub use gtk_rust_app_derive::*;
 #[cfg(feature = "ui")]
+pub use ui::dialogs;
+#[cfg(feature = "ui")]
 pub use ui::widgets;
 #[cfg(feature = "ui")]
 pub use ui::window::window;
@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Helpers to integrate gstore with the gstore debug window.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use glib::ObjectType;
+use gtk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(unix)]
+pub use crate::ui::debugging::DebugAddress;
+
+/// The name of the action which replaces the store state with a snapshot of the debug history.
+pub const REPLACE_STATE_ACTION: &str = "gstore-debug-replace-state";
+
+/// Set when `replaceable_reducer` wraps a reducer. Stores are usually created before the app.
+static REPLACEABLE_REDUCER: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static PENDING_STATE: RefCell<Option<Box<dyn Any>>> = RefCell::new(None);
+    /// The time the `replaceable_reducer` spent in the wrapped reducer for the last action.
+    static REDUCER_DURATION: Cell<Option<Duration>> = Cell::new(None);
+
+    /// The debug index of the action whose selector callbacks are currently profiled.
+    static PROFILED_ACTION: Cell<Option<usize>> = Cell::new(None);
+    static SELECTOR_PROFILES: RefCell<HashMap<usize, SelectorProfile>> = RefCell::new(HashMap::new());
+
+    /// The selectors of live widgets. `None` if the gstore debug window is not enabled.
+    static SUBSCRIPTIONS: RefCell<Option<Vec<Subscription>>> = RefCell::new(None);
+}
+
+/// Wrap the reducer of your store to allow the gstore debug window to replace the state
+/// (e.g. to jump to an earlier state of the action history) and to time the reducer.
+///
+/// ```rust,ignore
+/// gstore::store!(State);
+///
+/// fn main() {
+///     init_store(
+///         State::default(),
+///         gtk_rust_app::store::replaceable_reducer(reduce),
+///     );
+/// }
+/// ```
+pub fn replaceable_reducer<S: 'static>(
+    reducer: impl Fn(&gstore::Action, &mut S),
+) -> impl Fn(&gstore::Action, &mut S) {
+    REPLACEABLE_REDUCER.store(true, Ordering::Relaxed);
+    move |action, state| {
+        if action.name() == REPLACE_STATE_ACTION {
+            if let Some(new_state) = take_pending_state::<S>() {
+                *state = new_state;
+            }
+            return;
+        }
+        // Undo and redo restore a state which the undo middleware set before the reduce.
+        if action.name() == crate::undo::UNDO_ACTION || action.name() == crate::undo::REDO_ACTION {
+            if let Some(new_state) = take_pending_state::<S>() {
+                *state = new_state;
+                return;
+            }
+        }
+        let start = Instant::now();
+        reducer(action, state);
+        REDUCER_DURATION.with(|d| d.set(Some(start.elapsed())));
+    }
+}
+
+/// Whether a reducer was wrapped by `replaceable_reducer`. Replacing the state has no effect
+/// otherwise.
+pub(crate) fn has_replaceable_reducer() -> bool {
+    REPLACEABLE_REDUCER.load(Ordering::Relaxed)
+}
+
+/// Remove and return the time the `replaceable_reducer` spent in the reducer for the last
+/// action. `None` if the reducer is not wrapped.
+pub(crate) fn take_reducer_duration() -> Option<Duration> {
+    REDUCER_DURATION.with(|d| d.take())
+}
+
+/// Replace the state of the store on the next reduce via the `REPLACE_STATE_ACTION`.
+/// This has no effect if the store reducer is not wrapped by `replaceable_reducer`.
+pub(crate) fn replace_state<S: 'static>(
+    delegate: &glib::Sender<(gdk4::gio::SimpleAction, Option<glib::Variant>)>,
+    state: S,
+) {
+    set_pending_state(state);
+    let action = gdk4::gio::SimpleAction::new(REPLACE_STATE_ACTION, None);
+    if let Err(e) = delegate.send((action, None)) {
+        error!("Could not replace gstore state: {}", e);
+    }
+}
+
+/// Set the state which the `replaceable_reducer` applies on the next replacing action.
+pub(crate) fn set_pending_state<S: 'static>(state: S) {
+    PENDING_STATE.with(|pending| pending.replace(Some(Box::new(state))));
+}
+
+fn take_pending_state<S: 'static>() -> Option<S> {
+    PENDING_STATE
+        .with(|pending| pending.borrow_mut().take())
+        .and_then(|s| s.downcast::<S>().ok())
+        .map(|s| *s)
+}
+
+/// A recorded action. The argument is stored in the GVariant text format, e.g. `"'todo-1'"`
+/// or `"(1, true)"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedAction {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub argument: Option<String>,
+}
+
+impl RecordedAction {
+    pub fn from_action(action: &gstore::Action) -> Self {
+        Self {
+            name: action.name().to_string(),
+            argument: action.argument().map(|v| v.print(true).to_string()),
+        }
+    }
+
+    /// Parse the GVariant text of the argument.
+    pub fn argument_variant(&self) -> Result<Option<glib::Variant>, glib::Error> {
+        match &self.argument {
+            Some(text) => glib::Variant::parse(None, text).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn to_action(&self) -> Result<gstore::Action, glib::Error> {
+        Ok(gstore::Action::new(&self.name, self.argument_variant()?))
+    }
+}
+
+/// A sequence of recorded actions which can be exported from the gstore debug window and
+/// replayed against a fresh default state, e.g. to turn a bug report into a regression test.
+///
+/// ```rust,ignore
+/// #[test]
+/// fn issue_42() {
+///     let session = Session::load("tests/sessions/issue-42.json").unwrap();
+///     let state: State = gtk_rust_app::store::replay(&session, reduce).unwrap();
+///     assert_eq!(state.todos.len(), 2);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub actions: Vec<RecordedAction>,
+}
+
+impl Session {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = self
+            .to_json()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Replay a session headless: Reduce all actions with the given reducer starting at the default
+/// state. No store, window or main loop is required.
+pub fn replay<S: Default>(
+    session: &Session,
+    reducer: impl Fn(&gstore::Action, &mut S),
+) -> Result<S, glib::Error> {
+    let mut state = S::default();
+    for recorded in &session.actions {
+        reducer(&recorded.to_action()?, &mut state);
+    }
+    Ok(state)
+}
+
+/// Reset the store to the default state and dispatch all actions of the session.
+/// Resetting the state requires the store reducer to be wrapped by `replaceable_reducer`.
+pub(crate) fn replay_into_store<S: Default + 'static>(
+    delegate: &glib::Sender<(gdk4::gio::SimpleAction, Option<glib::Variant>)>,
+    session: &Session,
+) -> Result<(), glib::Error> {
+    // Parse all arguments first to not replay a partial session.
+    let actions = session
+        .actions
+        .iter()
+        .map(|a| a.argument_variant().map(|v| (a.name.as_str(), v)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    replace_state(delegate, S::default());
+    for (name, argument) in actions {
+        dispatch(delegate, name, argument);
+    }
+    Ok(())
+}
+
+/// Dispatch an action to the store as if the app action with the given name was activated.
+pub(crate) fn dispatch(
+    delegate: &glib::Sender<(gdk4::gio::SimpleAction, Option<glib::Variant>)>,
+    name: &str,
+    argument: Option<glib::Variant>,
+) {
+    let action = gdk4::gio::SimpleAction::new(name, argument.as_ref().map(|v| v.type_()));
+    if let Err(e) = delegate.send((action, argument)) {
+        error!("Could not dispatch action '{}': {}", name, e);
+    }
+}
+
+/// The `#[selector]` callbacks which fired for an action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelectorProfile {
+    /// The number of callbacks.
+    pub callbacks: usize,
+    /// The total time spent in the callbacks.
+    pub duration: Duration,
+}
+
+/// Attribute all following selector callbacks to the action with the given debug index
+/// (until the next action is profiled).
+pub(crate) fn profile_action(index: Option<usize>) {
+    PROFILED_ACTION.with(|a| a.set(index));
+}
+
+/// Remove and return the selector profile of the action with the given debug index.
+/// Following selector callbacks are not attributed to this action anymore.
+pub(crate) fn take_selector_profile(index: usize) -> SelectorProfile {
+    PROFILED_ACTION.with(|a| {
+        if a.get() == Some(index) {
+            a.set(None);
+        }
+    });
+    SELECTOR_PROFILES
+        .with(|p| p.borrow_mut().remove(&index))
+        .unwrap_or_default()
+}
+
+/// Run a selector callback. If the gstore debug window is active the callback is counted and
+/// timed for the current action. This is called by the code generated for `#[selector]`
+/// by gtk-rust-app-derive 0.3.
+#[doc(hidden)]
+pub fn profile_selector(widget: &gtk::Widget, selector: &str, f: impl FnOnce()) {
+    SUBSCRIPTIONS.with(|s| {
+        if let Some(subscriptions) = s.borrow_mut().as_mut() {
+            let address = widget.as_ptr() as usize;
+            if let Some(subscription) = subscriptions
+                .iter_mut()
+                .find(|s| s.address == address && s.selector == selector)
+            {
+                subscription.callbacks += 1;
+            }
+        }
+    });
+
+    let index = match PROFILED_ACTION.with(|a| a.get()) {
+        Some(index) => index,
+        None => return f(),
+    };
+    let start = Instant::now();
+    f();
+    let duration = start.elapsed();
+    SELECTOR_PROFILES.with(|p| {
+        let mut profiles = p.borrow_mut();
+        let profile = profiles.entry(index).or_default();
+        profile.callbacks += 1;
+        profile.duration += duration;
+    });
+}
+
+/// A `#[selector]` of a widget declared with `#[widget(@store ...)]`.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub widget: glib::WeakRef<gtk::Widget>,
+    /// Identifies the widget even while it is disposed.
+    address: usize,
+    /// The type name of the widget.
+    pub widget_type: &'static str,
+    /// The name of the selector function.
+    pub selector: &'static str,
+    /// The selected state paths, e.g. `state.todos`.
+    pub paths: Vec<&'static str>,
+    /// How often the selector callback fired.
+    pub callbacks: usize,
+}
+
+impl Subscription {
+    /// Identifies the widget of this subscription, even if it was finalized.
+    pub fn address(&self) -> usize {
+        self.address
+    }
+}
+
+/// Start tracking the selectors of widgets for the gstore debug window.
+pub(crate) fn enable_inspector() {
+    SUBSCRIPTIONS.with(|s| {
+        let mut s = s.borrow_mut();
+        if s.is_none() {
+            *s = Some(Vec::new());
+        }
+    });
+}
+
+/// The selectors of all widgets which were realized and not disposed yet.
+/// Empty if the gstore debug window is not enabled.
+pub fn subscriptions() -> Vec<Subscription> {
+    SUBSCRIPTIONS.with(|s| s.borrow().clone().unwrap_or_default())
+}
+
+/// Track a selector of a widget. This is called by the code generated for `#[selector]`.
+#[doc(hidden)]
+pub fn register_selector(
+    widget: &gtk::Widget,
+    widget_type: &'static str,
+    selector: &'static str,
+    paths: &[&'static str],
+) {
+    SUBSCRIPTIONS.with(|s| {
+        if let Some(subscriptions) = s.borrow_mut().as_mut() {
+            subscriptions.push(Subscription {
+                widget: widget.downgrade(),
+                address: widget.as_ptr() as usize,
+                widget_type,
+                selector,
+                paths: paths.to_vec(),
+                callbacks: 0,
+            });
+        }
+    });
+}
+
+/// Stop tracking the selectors of a widget. This is called when a `#[widget(@store ...)]` is
+/// disposed.
+#[doc(hidden)]
+pub fn unregister_widget(widget: &gtk::Widget) {
+    SUBSCRIPTIONS.with(|s| {
+        if let Some(subscriptions) = s.borrow_mut().as_mut() {
+            let address = widget.as_ptr() as usize;
+            subscriptions.retain(|s| s.address != address);
+        }
+    });
+}
+
+/// Open a gstore debug window which shows the events served by another app instance
+/// (see `AppBuilder::gstore_debug_remote`).
+///
+/// Jumping to a state, replaying and dispatching actions are not available remotely.
+#[cfg(unix)]
+pub fn attach_debug_window(address: &DebugAddress) -> std::io::Result<libadwaita::Window> {
+    let recv = crate::ui::debugging::attach(address)?;
+    let view = crate::builder::create_debug_view(recv);
+    Ok(crate::builder::create_debug_window(&view))
+}
@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A changed path in the state, e.g. `todos[2].done`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Compute the paths that differ between two serialized states.
+pub fn diff(before: &Value, after: &Value) -> Vec<StateChange> {
+    let mut changes = Vec::new();
+    diff_at(String::new(), before, after, &mut changes);
+    changes
+}
+
+fn diff_at(path: String, before: &Value, after: &Value, changes: &mut Vec<StateChange>) {
+    match (before, after) {
+        (Value::Object(b), Value::Object(a)) => {
+            for (key, bv) in b {
+                let p = join_key(&path, key);
+                match a.get(key) {
+                    Some(av) => diff_at(p, bv, av, changes),
+                    None => changes.push(StateChange {
+                        path: p,
+                        kind: ChangeKind::Removed,
+                        before: Some(bv.clone()),
+                        after: None,
+                    }),
+                }
+            }
+            for (key, av) in a {
+                if !b.contains_key(key) {
+                    changes.push(StateChange {
+                        path: join_key(&path, key),
+                        kind: ChangeKind::Added,
+                        before: None,
+                        after: Some(av.clone()),
+                    });
+                }
+            }
+        }
+        (Value::Array(b), Value::Array(a)) => {
+            for i in 0..b.len().max(a.len()) {
+                let p = format!("{}[{}]", path, i);
+                match (b.get(i), a.get(i)) {
+                    (Some(bv), Some(av)) => diff_at(p, bv, av, changes),
+                    (Some(bv), None) => changes.push(StateChange {
+                        path: p,
+                        kind: ChangeKind::Removed,
+                        before: Some(bv.clone()),
+                        after: None,
+                    }),
+                    (None, Some(av)) => changes.push(StateChange {
+                        path: p,
+                        kind: ChangeKind::Added,
+                        before: None,
+                        after: Some(av.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (b, a) => {
+            if b != a {
+                changes.push(StateChange {
+                    path: if path.is_empty() { "$".into() } else { path },
+                    kind: ChangeKind::Changed,
+                    before: Some(b.clone()),
+                    after: Some(a.clone()),
+                });
+            }
+        }
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(
+        path: &str,
+        kind: ChangeKind,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> StateChange {
+        StateChange {
+            path: path.into(),
+            kind,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn diff_of_equal_states_is_empty() {
+        let state = json!({ "todos": [{ "done": false }], "filter": null });
+        assert!(diff(&state, &state).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_nested_object_changes() {
+        let before = json!({ "settings": { "theme": { "dark": false, "accent": "blue" } } });
+        let after = json!({ "settings": { "theme": { "dark": true, "accent": "blue" } } });
+        assert_eq!(
+            diff(&before, &after),
+            vec![change(
+                "settings.theme.dark",
+                ChangeKind::Changed,
+                Some(json!(false)),
+                Some(json!(true)),
+            )]
+        );
+    }
+
+    #[test]
+    fn diff_reports_added_and_removed_keys() {
+        let before = json!({ "a": 1, "b": 2 });
+        let after = json!({ "b": 2, "c": 3 });
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                change("a", ChangeKind::Removed, Some(json!(1)), None),
+                change("c", ChangeKind::Added, None, Some(json!(3))),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_reports_array_length_changes() {
+        let before = json!({ "todos": ["a", "b"] });
+        let longer = json!({ "todos": ["a", "b", "c"] });
+        let shorter = json!({ "todos": ["x"] });
+        assert_eq!(
+            diff(&before, &longer),
+            vec![change(
+                "todos[2]",
+                ChangeKind::Added,
+                None,
+                Some(json!("c"))
+            )]
+        );
+        assert_eq!(
+            diff(&before, &shorter),
+            vec![
+                change(
+                    "todos[0]",
+                    ChangeKind::Changed,
+                    Some(json!("a")),
+                    Some(json!("x")),
+                ),
+                change("todos[1]", ChangeKind::Removed, Some(json!("b")), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_reports_scalar_type_changes() {
+        let before = json!({ "count": 1, "items": [1] });
+        let after = json!({ "count": "1", "items": { "0": 1 } });
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                change(
+                    "count",
+                    ChangeKind::Changed,
+                    Some(json!(1)),
+                    Some(json!("1")),
+                ),
+                change(
+                    "items",
+                    ChangeKind::Changed,
+                    Some(json!([1])),
+                    Some(json!({ "0": 1 })),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_from_null_replaces_the_whole_state() {
+        let after = json!({ "todos": [] });
+        assert_eq!(
+            diff(&Value::Null, &after),
+            vec![change(
+                "$",
+                ChangeKind::Changed,
+                Some(Value::Null),
+                Some(after.clone())
+            )]
+        );
+        assert_eq!(
+            diff(&json!({ "selected": null }), &json!({ "selected": 3 })),
+            vec![change(
+                "selected",
+                ChangeKind::Changed,
+                Some(Value::Null),
+                Some(json!(3)),
+            )]
+        );
+    }
+}
@@ -1,14 +1,75 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use adw::traits::{ActionRowExt, PreferencesRowExt};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use adw::traits::{ActionRowExt, ExpanderRowExt, PreferencesRowExt};
 use gdk4::subclass::prelude::ObjectSubclassIsExt;
 use gtk::{prelude::*, TemplateChild};
 use gtk_rust_app_derive::widget;
 use libadwaita as adw;
+use serde_json::Value;
+
+use super::{ChangeKind, StateChange};
+use crate::store::{RecordedAction, Session, Subscription};
+
+/// A reduced action as it is sent from the debugging middleware to the debug window.
+#[derive(Debug, Clone)]
+pub struct DebugEvent {
+    /// The running number of the action.
+    pub index: usize,
+    pub action: gstore::Action,
+    /// The state after the action was reduced.
+    pub state: String,
+    /// The state after the action as JSON if the store state is serializable.
+    pub state_json: Option<Value>,
+    /// The paths which were changed by the action. Empty if the state is not serializable.
+    pub changes: Vec<StateChange>,
+    pub metrics: ActionMetrics,
+}
+
+/// Timing of a reduced action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ActionMetrics {
+    /// The time spent in the reducer. Zero if it is not wrapped by `replaceable_reducer`.
+    pub reducer_duration: Duration,
+    /// The number of `#[selector]` callbacks which fired for the action.
+    pub selector_callbacks: usize,
+    /// The total time spent in the `#[selector]` callbacks.
+    pub selector_duration: Duration,
+}
+
+impl ActionMetrics {
+    pub fn total(&self) -> Duration {
+        self.reducer_duration + self.selector_duration
+    }
+}
+
+/// The number of actions in the "Slowest actions" summary.
+const SLOWEST_ACTIONS: usize = 5;
+
+pub type TimeTravelHandler = Box<dyn Fn(Option<usize>)>;
+pub type ReplayHandler = Box<dyn Fn(&Session) -> Result<(), glib::Error>>;
+pub type DispatchHandler = Box<dyn Fn(&str, Option<glib::Variant>)>;
 
 #[widget(extends gtk::Box)]
 #[template(file = "gstore_debug.ui")]
 pub struct GstoreDebug {
+    #[template_child]
+    pub debug_stack: TemplateChild<adw::ViewStack>,
+    #[template_child]
+    pub widgets_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    pub widgets_list: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub refresh_widgets_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub slowest_list: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub export_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub import_button: TemplateChild<gtk::Button>,
     #[template_child]
     pub actions_list: TemplateChild<gtk::ListBox>,
     #[template_child]
@@ -16,21 +77,86 @@ pub struct GstoreDebug {
     #[template_child]
     pub scrolled_window: TemplateChild<gtk::ScrolledWindow>,
 
+    #[template_child]
+    pub dispatch_action_entry: TemplateChild<gtk::Entry>,
+    #[template_child]
+    pub dispatch_argument_entry: TemplateChild<gtk::Entry>,
+    #[template_child]
+    pub dispatch_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub dispatch_error_label: TemplateChild<gtk::Label>,
+
+    #[template_child]
+    pub state_stack: TemplateChild<gtk::Stack>,
+    #[template_child]
+    pub diff_page: TemplateChild<gtk::StackPage>,
+    #[template_child]
+    pub diff_list: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub tree_page: TemplateChild<gtk::StackPage>,
+    #[template_child]
+    pub tree_box: TemplateChild<gtk::Box>,
     #[template_child]
     pub state_text: TemplateChild<gtk::TextView>,
+    #[template_child]
+    pub state_label: TemplateChild<gtk::Label>,
+    #[template_child]
+    pub jump_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub resume_button: TemplateChild<gtk::Button>,
 
+    #[signal_handler(refresh_widgets_button clicked)]
+    pub on_refresh_widgets: (),
+    #[signal_handler(export_button clicked)]
+    pub on_export: (),
+    #[signal_handler(import_button clicked)]
+    pub on_import: (),
+    #[signal_handler(dispatch_action_entry changed)]
+    pub on_dispatch_action_changed: (),
+    #[signal_handler(dispatch_argument_entry changed)]
+    pub on_dispatch_argument_changed: (),
+    #[signal_handler(dispatch_argument_entry activate)]
+    pub on_dispatch_argument_activate: (),
+    #[signal_handler(dispatch_button clicked)]
+    pub on_dispatch: (),
     #[signal_handler(filter_entry changed)]
     pub on_filter: (),
+    #[signal_handler(jump_button clicked)]
+    pub on_jump: (),
+    #[signal_handler(resume_button clicked)]
+    pub on_resume: (),
+
+    /// Events by action index. Bounded by `max_history`.
+    pub states: RefCell<VecDeque<DebugEvent>>,
+    pub max_history: Cell<usize>,
+    /// The action whose state is shown instead of the latest state.
+    pub selected: Cell<Option<usize>>,
+    /// The event whose state is shown. Only the visible state view is built for it.
+    pub displayed: RefCell<Option<DebugEvent>>,
+    /// The state views (`diff`, `tree`, `raw`) which are up to date with `displayed`.
+    pub rendered: RefCell<Vec<String>>,
+    pub time_travel_handler: RefCell<Option<TimeTravelHandler>>,
+
+    /// The first `max_history` actions since the start or the last replay. Recording stops
+    /// then so that an exported session still starts at the default state.
+    pub recorded: RefCell<Vec<RecordedAction>>,
+    pub replay_handler: RefCell<Option<ReplayHandler>>,
+    pub file_chooser: RefCell<Option<gtk::FileChooserNative>>,
+
+    /// The actions of the manifest with their declared argument type.
+    pub dispatchable_actions: RefCell<Vec<(String, Option<String>)>>,
+    pub dispatch_handler: RefCell<Option<DispatchHandler>>,
 }
 
 impl GstoreDebug {
-    pub fn new(recv: Option<glib::Receiver<(gstore::Action, String)>>) -> Self {
+    pub fn new(recv: Option<glib::Receiver<DebugEvent>>) -> Self {
         let _self: Self = glib::Object::new(&[]).expect("Failed to create GstoreDebug");
+        _self.imp().max_history.set(100);
         if let Some(recv) = recv {
             recv.attach(
                 None,
-                glib::clone!(@weak _self => @default-return glib::Continue(true), move |(a, s)| {
-                    _self.on_action(a, s);
+                glib::clone!(@weak _self => @default-return glib::Continue(true), move |event| {
+                    _self.on_action(event);
                     glib::Continue(true)
                 }),
             );
@@ -38,7 +164,341 @@ impl GstoreDebug {
         _self
     }
 
-    pub fn constructed(&self) {}
+    pub fn constructed(&self) {
+        let s = self;
+        self.debug_stack().connect_visible_child_name_notify(
+            glib::clone!(@weak s => move |stack| {
+                if stack.visible_child_name().as_deref() == Some("widgets") {
+                    s.update_widgets();
+                }
+            }),
+        );
+        self.state_stack()
+            .connect_visible_child_name_notify(glib::clone!(@weak s => move |_| {
+                s.render_state();
+            }));
+        self.connect_map(|s| s.render_state());
+    }
+
+    fn on_refresh_widgets(&self, _: gtk::Button) {
+        self.update_widgets();
+    }
+
+    /// List the live widgets with their selectors. Widgets which are not part of a window
+    /// anymore but still subscribed are listed first since they are likely leaked.
+    fn update_widgets(&self) {
+        let mut widgets: Vec<(WidgetStatus, Vec<Subscription>)> = Vec::new();
+        for subscription in crate::store::subscriptions() {
+            let existing = widgets
+                .iter_mut()
+                .find(|(_, subs)| subs[0].address() == subscription.address());
+            match existing {
+                Some((_, subs)) => subs.push(subscription),
+                None => widgets.push((WidgetStatus::of(&subscription), vec![subscription])),
+            }
+        }
+        widgets.sort_by_key(|(status, _)| *status);
+
+        let list = self.widgets_list();
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+        let leaked = widgets
+            .iter()
+            .filter(|(status, _)| *status != WidgetStatus::Mapped)
+            .count();
+        self.widgets_label().set_label(&format!(
+            "{} subscribed widgets, {} not visible",
+            widgets.len(),
+            leaked
+        ));
+        for (status, subscriptions) in widgets {
+            list.append(&widget_row(status, &subscriptions));
+        }
+    }
+
+    /// Set the number of actions whose state is kept.
+    pub fn set_max_history(&self, max_history: usize) {
+        self.imp().max_history.set(max_history);
+    }
+
+    /// Set the function which replaces the store state with the snapshot of the given action
+    /// index or with the latest state if the index is `None`.
+    pub fn set_time_travel_handler(&self, f: impl Fn(Option<usize>) + 'static) {
+        self.imp().time_travel_handler.replace(Some(Box::new(f)));
+    }
+
+    /// Set the function which resets the store to the default state and dispatches the actions
+    /// of an imported session.
+    pub fn set_replay_handler(&self, f: impl Fn(&Session) -> Result<(), glib::Error> + 'static) {
+        self.imp().replay_handler.replace(Some(Box::new(f)));
+    }
+
+    /// Set the actions which can be dispatched from the debug window with their declared
+    /// argument type (see `ProjectDescriptor.actions`).
+    pub fn set_dispatchable_actions(&self, actions: Vec<(String, Option<String>)>) {
+        let mut actions = actions;
+        actions.sort();
+
+        let model = gtk::ListStore::new(&[glib::Type::STRING]);
+        for (name, _) in &actions {
+            model.set(&model.append(), &[(0, name)]);
+        }
+        let completion = gtk::EntryCompletion::new();
+        completion.set_model(Some(&model));
+        completion.set_text_column(0);
+        completion.set_inline_completion(true);
+        completion.set_popup_completion(true);
+        self.dispatch_action_entry()
+            .set_completion(Some(&completion));
+
+        self.imp().dispatchable_actions.replace(actions);
+    }
+
+    /// Set the function which dispatches an action with its argument to the store.
+    pub fn set_dispatch_handler(&self, f: impl Fn(&str, Option<glib::Variant>) + 'static) {
+        self.imp().dispatch_handler.replace(Some(Box::new(f)));
+    }
+
+    /// The declared argument type of the entered action or `Err` if the action is unknown.
+    fn dispatch_action_type(&self) -> Result<Option<String>, String> {
+        let name = self.dispatch_action_entry().text().to_string();
+        self.imp()
+            .dispatchable_actions
+            .borrow()
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, t)| t.clone())
+            .ok_or_else(|| format!("Unknown action '{}'", name))
+    }
+
+    /// Parse the entered argument and validate it against the declared type of the action.
+    fn dispatch_argument(&self) -> Result<Option<glib::Variant>, String> {
+        let text = self.dispatch_argument_entry().text().to_string();
+        match self.dispatch_action_type()? {
+            None if text.trim().is_empty() => Ok(None),
+            None => Err("This action takes no argument".into()),
+            Some(t) => {
+                let ty = glib::VariantTy::new(&t).map_err(|e| e.to_string())?;
+                glib::Variant::parse(Some(ty), &text)
+                    .map(Some)
+                    .map_err(|e| format!("Expected argument of type '{}': {}", t, e))
+            }
+        }
+    }
+
+    fn validate_dispatch(&self) {
+        let action_type = self.dispatch_action_type();
+        let argument_entry = self.dispatch_argument_entry();
+        match &action_type {
+            Ok(Some(t)) => {
+                argument_entry.set_sensitive(true);
+                argument_entry.set_placeholder_text(Some(&format!("Argument of type {}", t)));
+            }
+            _ => {
+                argument_entry.set_sensitive(false);
+                argument_entry.set_placeholder_text(Some("Argument"));
+            }
+        }
+
+        let empty = self.dispatch_action_entry().text().is_empty()
+            || (matches!(action_type, Ok(Some(_))) && argument_entry.text().is_empty());
+        let result = self.dispatch_argument();
+        self.dispatch_button().set_sensitive(result.is_ok());
+        match result {
+            Err(e) if !empty => {
+                self.dispatch_error_label().set_label(&e);
+                self.dispatch_error_label().set_visible(true);
+            }
+            _ => self.dispatch_error_label().set_visible(false),
+        }
+    }
+
+    fn on_dispatch_action_changed(&self, _: gtk::Entry) {
+        self.validate_dispatch();
+    }
+
+    fn on_dispatch_argument_changed(&self, _: gtk::Entry) {
+        self.validate_dispatch();
+    }
+
+    fn on_dispatch_argument_activate(&self, _: gtk::Entry) {
+        self.dispatch();
+    }
+
+    fn on_dispatch(&self, _: gtk::Button) {
+        self.dispatch();
+    }
+
+    fn dispatch(&self) {
+        let argument = match self.dispatch_argument() {
+            Ok(argument) => argument,
+            Err(_) => return,
+        };
+        let name = self.dispatch_action_entry().text().to_string();
+        if let Some(handler) = self.imp().dispatch_handler.borrow().as_ref() {
+            handler(&name, argument);
+        } else {
+            warn!("Can not dispatch '{}': No dispatch handler set.", name);
+        }
+    }
+
+    /// The actions recorded since the start or the last replay.
+    pub fn session(&self) -> Session {
+        Session {
+            actions: self.imp().recorded.borrow().clone(),
+        }
+    }
+
+    fn on_export(&self, _: gtk::Button) {
+        let s = self;
+        self.choose_file(
+            gtk::FileChooserAction::Save,
+            "Export",
+            glib::clone!(@weak s => move |path| {
+                match s.session().save(&path) {
+                    Ok(_) => s.state_label().set_label(&format!(
+                        "Exported {} actions",
+                        s.imp().recorded.borrow().len()
+                    )),
+                    Err(e) => {
+                        error!("Could not export gstore session to {:?}: {}", path, e);
+                        s.state_label().set_label(&format!("Export failed: {}", e));
+                    }
+                }
+            }),
+        );
+    }
+
+    fn on_import(&self, _: gtk::Button) {
+        let s = self;
+        self.choose_file(
+            gtk::FileChooserAction::Open,
+            "Replay",
+            glib::clone!(@weak s => move |path| {
+                match Session::load(&path) {
+                    Ok(session) => s.replay(&session),
+                    Err(e) => {
+                        error!("Could not import gstore session from {:?}: {}", path, e);
+                        s.state_label().set_label(&format!("Import failed: {}", e));
+                    }
+                }
+            }),
+        );
+    }
+
+    /// Reset the store to its default state and dispatch all actions of the given session.
+    pub fn replay(&self, session: &Session) {
+        let result = match self.imp().replay_handler.borrow().as_ref() {
+            Some(handler) => handler(session),
+            None => {
+                warn!("Can not replay gstore session: No replay handler set.");
+                return;
+            }
+        };
+        match result {
+            Ok(_) => {
+                // The replayed actions are recorded again as they are reduced.
+                self.imp().recorded.borrow_mut().clear();
+                self.imp().selected.set(None);
+                self.resume_button().set_visible(false);
+                self.jump_button().set_sensitive(false);
+                self.state_label()
+                    .set_label(&format!("Replaying {} actions", session.actions.len()));
+            }
+            Err(e) => {
+                error!("Could not replay gstore session: {}", e);
+                self.state_label()
+                    .set_label(&format!("Replay failed: {}", e));
+            }
+        }
+    }
+
+    fn choose_file(
+        &self,
+        action: gtk::FileChooserAction,
+        accept_label: &str,
+        f: impl Fn(std::path::PathBuf) + 'static,
+    ) {
+        let window = self.root().and_then(|r| r.downcast::<gtk::Window>().ok());
+        let dialog = gtk::FileChooserNative::new(
+            Some(accept_label),
+            window.as_ref(),
+            action,
+            Some(accept_label),
+            None,
+        );
+        if action == gtk::FileChooserAction::Save {
+            dialog.set_current_name("gstore-session.json");
+        }
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some("JSON"));
+        filter.add_pattern("*.json");
+        dialog.add_filter(&filter);
+
+        let s = self;
+        dialog.connect_response(glib::clone!(@weak s => move |d, response| {
+            if response == gtk::ResponseType::Accept {
+                if let Some(path) = d.file().and_then(|f| f.path()) {
+                    f(path);
+                }
+            }
+            s.imp().file_chooser.replace(None);
+        }));
+        dialog.show();
+        // Native dialogs are not kept alive by GTK.
+        self.imp().file_chooser.replace(Some(dialog));
+    }
+
+    fn on_jump(&self, _: gtk::Button) {
+        if let Some(index) = self.imp().selected.get() {
+            if let Some(handler) = self.imp().time_travel_handler.borrow().as_ref() {
+                handler(Some(index));
+            }
+            self.state_label()
+                .set_label(&format!("Jumped to #{} (store state replaced)", index));
+            self.resume_button().set_visible(true);
+        }
+    }
+
+    fn on_resume(&self, _: gtk::Button) {
+        if let Some(handler) = self.imp().time_travel_handler.borrow().as_ref() {
+            handler(None);
+        }
+        self.imp().selected.set(None);
+        self.resume_button().set_visible(false);
+        self.jump_button().set_sensitive(false);
+        self.actions_list().unselect_all();
+        self.state_label().set_label("Live");
+        let latest = self.imp().states.borrow().back().cloned();
+        if let Some(event) = latest {
+            self.show_event(event);
+        }
+    }
+
+    fn select(&self, index: usize) {
+        let event = self
+            .imp()
+            .states
+            .borrow()
+            .iter()
+            .find(|e| e.index == index)
+            .cloned();
+        match event {
+            Some(event) => {
+                self.imp().selected.set(Some(index));
+                self.show_event(event);
+                self.state_label()
+                    .set_label(&format!("State after #{}", index));
+                self.jump_button().set_sensitive(true);
+            }
+            None => {
+                self.state_label()
+                    .set_label(&format!("#{} is not in the history anymore", index));
+                self.jump_button().set_sensitive(false);
+            }
+        }
+    }
 
     fn on_filter(&self, _: gtk::Entry) {
         self.filter();
@@ -72,8 +532,73 @@ impl GstoreDebug {
         }
     }
 
-    fn on_action(&self, action: gstore::Action, state: String) {
-        let mut row_builder = adw::ActionRow::builder().title(action.name());
+    /// Show the diff, JSON tree and raw state of the given event. The views are built when
+    /// they become visible.
+    fn show_event(&self, event: DebugEvent) {
+        let has_json = event.state_json.is_some();
+        self.imp().displayed.replace(Some(event));
+        self.imp().rendered.borrow_mut().clear();
+
+        self.diff_page().set_visible(has_json);
+        self.tree_page().set_visible(has_json);
+        if !has_json {
+            self.state_stack().set_visible_child_name("raw");
+        }
+        self.render_state();
+    }
+
+    /// Build the visible state view for the displayed event if the window is visible.
+    fn render_state(&self) {
+        if !self.is_mapped() {
+            return;
+        }
+        let view = match self.state_stack().visible_child_name() {
+            Some(view) => view.to_string(),
+            None => return,
+        };
+        if self.imp().rendered.borrow().contains(&view) {
+            return;
+        }
+        let displayed = self.imp().displayed.borrow();
+        let event = match displayed.as_ref() {
+            Some(event) => event,
+            None => return,
+        };
+
+        match (view.as_str(), &event.state_json) {
+            ("diff", Some(_)) => {
+                let diff_list = self.diff_list();
+                while let Some(child) = diff_list.first_child() {
+                    diff_list.remove(&child);
+                }
+                if event.changes.is_empty() {
+                    let row = adw::ActionRow::builder().title("No changes").build();
+                    diff_list.append(&row);
+                }
+                for change in &event.changes {
+                    diff_list.append(&diff_row(change));
+                }
+            }
+            ("tree", Some(json)) => {
+                let tree_box = self.tree_box();
+                while let Some(child) = tree_box.first_child() {
+                    tree_box.remove(&child);
+                }
+                tree_box.append(&json_tree("state", json, &event.changes, ""));
+            }
+            ("raw", _) => self.state_text().buffer().set_text(&event.state),
+            _ => return,
+        }
+        self.imp().rendered.borrow_mut().push(view);
+    }
+
+    fn on_action(&self, event: DebugEvent) {
+        let index = event.index;
+        let action = event.action.clone();
+        let mut row_builder = adw::ActionRow::builder()
+            .title(action.name())
+            .activatable(true)
+            .name(&index.to_string());
         if let Some(d) = glib::DateTime::now(&glib::TimeZone::utc())
             .ok()
             .and_then(|d| d.format_iso8601().ok())
@@ -81,11 +606,16 @@ impl GstoreDebug {
             row_builder = row_builder.subtitle(&d);
         }
         let row = row_builder.build();
+        let s = self;
+        row.connect_activated(glib::clone!(@weak s => move |_| {
+            s.select(index);
+        }));
         if let Some(v) = action.argument() {
             let label = gtk::Label::new(Some(&format!("{}", v)));
             label.set_ellipsize(gdk4::pango::EllipsizeMode::End);
             row.add_suffix(&label);
         }
+        add_metrics_columns(&row, &event.metrics);
 
         let scroll_y = self.scrolled_window().vadjustment().value();
         let row_height = 50.0;
@@ -100,9 +630,264 @@ impl GstoreDebug {
                 .set_value(scroll_y + row_height);
         }
 
-        let buf = self.state_text().buffer();
-        buf.set_text(&state);
+        {
+            let mut states = self.imp().states.borrow_mut();
+            states.push_back(event.clone());
+            let mut recorded = self.imp().recorded.borrow_mut();
+            if recorded.len() < self.imp().max_history.get() {
+                recorded.push(RecordedAction::from_action(&action));
+                if recorded.len() == self.imp().max_history.get() {
+                    self.state_label().set_label(&format!(
+                        "Recording stopped after {} actions",
+                        recorded.len()
+                    ));
+                }
+            }
+            while states.len() > self.imp().max_history.get() {
+                states.pop_front();
+                if let Some(oldest) = self.actions_list().last_child() {
+                    self.actions_list().remove(&oldest);
+                }
+            }
+        }
+
+        self.update_slowest();
+        if self.debug_stack().visible_child_name().as_deref() == Some("widgets") {
+            self.update_widgets();
+        }
+
+        if self.imp().selected.get().is_none() {
+            self.show_event(event);
+        }
+    }
+
+    /// Show the slowest actions of the history by reducer and selector time.
+    fn update_slowest(&self) {
+        let mut events: Vec<(usize, String, ActionMetrics)> = self
+            .imp()
+            .states
+            .borrow()
+            .iter()
+            .map(|e| (e.index, e.action.name().to_string(), e.metrics))
+            .collect();
+        events.sort_by(|a, b| b.2.total().cmp(&a.2.total()));
+
+        let list = self.slowest_list();
+        while let Some(child) = list.first_child() {
+            list.remove(&child);
+        }
+        for (index, name, metrics) in events.into_iter().take(SLOWEST_ACTIONS) {
+            let row = adw::ActionRow::builder()
+                .title(&name)
+                .subtitle(&format!(
+                    "#{} · {}",
+                    index,
+                    format_duration(metrics.total())
+                ))
+                .activatable(true)
+                .build();
+            add_metrics_columns(&row, &metrics);
+            let s = self;
+            row.connect_activated(glib::clone!(@weak s => move |_| {
+                s.select(index);
+            }));
+            list.append(&row);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum WidgetStatus {
+    /// The widget was finalized without unsubscribing.
+    Finalized,
+    /// The widget is alive but not part of a window.
+    Detached,
+    /// The widget is part of a window but not mapped.
+    Unmapped,
+    Mapped,
+}
+
+impl WidgetStatus {
+    fn of(subscription: &Subscription) -> Self {
+        match subscription.widget.upgrade() {
+            None => WidgetStatus::Finalized,
+            Some(w) if w.root().is_none() => WidgetStatus::Detached,
+            Some(w) if !w.is_mapped() => WidgetStatus::Unmapped,
+            Some(_) => WidgetStatus::Mapped,
+        }
+    }
+
+    fn label(&self) -> (&'static str, Option<&'static str>) {
+        match self {
+            WidgetStatus::Finalized => ("finalized", Some("error")),
+            WidgetStatus::Detached => ("detached", Some("warning")),
+            WidgetStatus::Unmapped => ("unmapped", None),
+            WidgetStatus::Mapped => ("mapped", Some("success")),
+        }
+    }
+}
+
+fn widget_row(status: WidgetStatus, subscriptions: &[Subscription]) -> adw::ExpanderRow {
+    let callbacks: usize = subscriptions.iter().map(|s| s.callbacks).sum();
+    let widget_name = subscriptions[0]
+        .widget
+        .upgrade()
+        .map(|w| w.widget_name().to_string())
+        .unwrap_or_default();
+    let row = adw::ExpanderRow::builder()
+        .title(subscriptions[0].widget_type)
+        .subtitle(&format!(
+            "{} · {} selectors · {} callbacks",
+            widget_name,
+            subscriptions.len(),
+            callbacks
+        ))
+        .build();
+
+    let (text, class) = status.label();
+    let label = gtk::Label::new(Some(text));
+    if let Some(class) = class {
+        label.add_css_class(class);
+    }
+    row.add_action(&label);
+
+    for subscription in subscriptions {
+        let selector_row = adw::ActionRow::builder()
+            .title(subscription.selector)
+            .subtitle(&subscription.paths.join(", "))
+            .build();
+        let label = gtk::Label::new(Some(&format!("{} cb", subscription.callbacks)));
+        label.add_css_class("dim-label");
+        label.add_css_class("numeric");
+        selector_row.add_suffix(&label);
+        row.add_row(&selector_row);
+    }
+    row
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.2} ms", d.as_secs_f64() * 1000.0)
+}
+
+/// Add the reducer time, the number of selector callbacks and the selector time as columns.
+fn add_metrics_columns(row: &adw::ActionRow, metrics: &ActionMetrics) {
+    let columns = [
+        (
+            format_duration(metrics.reducer_duration),
+            "Reducer duration",
+        ),
+        (
+            format!("{} cb", metrics.selector_callbacks),
+            "Number of #[selector] callbacks",
+        ),
+        (
+            format_duration(metrics.selector_duration),
+            "Time spent in #[selector] callbacks",
+        ),
+    ];
+    for (text, tooltip) in columns {
+        let label = gtk::Label::new(Some(&text));
+        label.set_width_chars(8);
+        label.set_xalign(1.0);
+        label.set_tooltip_text(Some(tooltip));
+        label.add_css_class("dim-label");
+        label.add_css_class("numeric");
+        row.add_suffix(&label);
+    }
+}
+
+fn short_value(value: &Option<Value>) -> String {
+    match value {
+        Some(v) => {
+            let s = v.to_string();
+            if s.chars().count() > 60 {
+                format!("{}…", s.chars().take(60).collect::<String>())
+            } else {
+                s
+            }
+        }
+        None => "—".into(),
+    }
+}
+
+fn diff_row(change: &StateChange) -> adw::ActionRow {
+    let (kind, class) = match change.kind {
+        ChangeKind::Added => ("added", "success"),
+        ChangeKind::Removed => ("removed", "error"),
+        ChangeKind::Changed => ("changed", "warning"),
+    };
+    let row = adw::ActionRow::builder()
+        .title(&change.path)
+        .subtitle(&format!(
+            "{} → {}",
+            short_value(&change.before),
+            short_value(&change.after)
+        ))
+        .build();
+    let label = gtk::Label::new(Some(kind));
+    label.add_css_class(class);
+    row.add_suffix(&label);
+    row
+}
+
+/// Whether `path` is `parent` or one of its descendants.
+fn is_within(path: &str, parent: &str) -> bool {
+    match path.strip_prefix(parent) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+        None => false,
+    }
+}
+
+/// Build a collapsible tree of the given JSON value. Nodes on a changed path are expanded and
+/// highlighted.
+fn json_tree(key: &str, value: &Value, changes: &[StateChange], path: &str) -> gtk::Widget {
+    let changed = path.is_empty() || changes.iter().any(|c| is_within(&c.path, path));
+    let children: Vec<(String, String, &Value)> = match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| {
+                let p = if path.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{}.{}", path, k)
+                };
+                (k.clone(), p, v)
+            })
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("[{}]", i), format!("{}[{}]", path, i), v))
+            .collect(),
+        _ => {
+            let label = gtk::Label::new(Some(&format!("{}: {}", key, value)));
+            label.set_xalign(0.0);
+            label.set_selectable(true);
+            label.set_wrap(true);
+            label.add_css_class("monospace");
+            if !path.is_empty() && changes.iter().any(|c| c.path == path) {
+                label.add_css_class("warning");
+            }
+            return label.upcast();
+        }
+    };
+
+    let summary = match value {
+        Value::Array(items) => format!("{} [{}]", key, items.len()),
+        _ => format!("{} {{{}}}", key, children.len()),
+    };
+    let expander = gtk::Expander::new(Some(&summary));
+    expander.set_expanded(changed);
+    if !path.is_empty() && changes.iter().any(|c| c.path == path) {
+        expander.add_css_class("warning");
+    }
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    content.set_margin_start(16);
+    for (key, p, v) in children {
+        content.append(&json_tree(&key, v, changes, &p));
     }
+    expander.set_child(Some(&content));
+    expander.upcast()
 }
 
 impl Default for GstoreDebug {
@@ -1,2 +1,10 @@
+mod diff;
 mod gstore_debug;
+#[cfg(unix)]
+mod remote;
+pub use diff::*;
 pub use gstore_debug::*;
+#[cfg(unix)]
+pub use remote::DebugAddress;
+#[cfg(unix)]
+pub(crate) use remote::{attach, DebugServer};
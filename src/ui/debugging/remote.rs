@@ -0,0 +1,196 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Serve gstore debug events as JSON lines over a local socket and attach to them.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{ActionMetrics, DebugEvent, StateChange};
+use crate::store::RecordedAction;
+
+/// Where the gstore debug middleware serves its events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugAddress {
+    /// A Unix socket at the given path.
+    Unix(PathBuf),
+    /// A TCP port on `127.0.0.1`.
+    Tcp(u16),
+}
+
+/// A `DebugEvent` as it is sent over the socket. One JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RemoteEvent {
+    index: usize,
+    action: RecordedAction,
+    state: String,
+    state_json: Option<Value>,
+    changes: Vec<StateChange>,
+    metrics: ActionMetrics,
+}
+
+impl From<&DebugEvent> for RemoteEvent {
+    fn from(event: &DebugEvent) -> Self {
+        Self {
+            index: event.index,
+            action: RecordedAction::from_action(&event.action),
+            state: event.state.clone(),
+            state_json: event.state_json.clone(),
+            changes: event.changes.clone(),
+            metrics: event.metrics,
+        }
+    }
+}
+
+impl RemoteEvent {
+    fn into_debug_event(self) -> Result<DebugEvent, glib::Error> {
+        Ok(DebugEvent {
+            index: self.index,
+            action: self.action.to_action()?,
+            state: self.state,
+            state_json: self.state_json,
+            changes: self.changes,
+            metrics: self.metrics,
+        })
+    }
+}
+
+/// The number of events which are queued for a client. Clients which fall further behind
+/// are disconnected.
+const CLIENT_QUEUE: usize = 256;
+
+type Clients = Arc<Mutex<Vec<mpsc::SyncSender<Arc<str>>>>>;
+
+/// Accepts clients on a local socket and writes each debug event to all of them.
+pub(crate) struct DebugServer {
+    clients: Clients,
+}
+
+impl DebugServer {
+    pub fn start(address: &DebugAddress) -> std::io::Result<Self> {
+        let clients: Clients = Default::default();
+
+        match address {
+            DebugAddress::Unix(path) => {
+                // A socket of a previous run is not removed on exit. Never remove anything else.
+                if let Ok(metadata) = std::fs::symlink_metadata(path) {
+                    if !metadata.file_type().is_socket() {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::AlreadyExists,
+                            format!("{:?} exists and is not a socket", path),
+                        ));
+                    }
+                    // Only a socket nobody listens on anymore is stale.
+                    match UnixStream::connect(path) {
+                        Ok(_) => {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::AddrInUse,
+                                format!("{:?} is in use by another instance", path),
+                            ))
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                            std::fs::remove_file(path)?
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                let listener = UnixListener::bind(path)?;
+                let c = clients.clone();
+                std::thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        add_client(&c, stream);
+                    }
+                });
+            }
+            DebugAddress::Tcp(port) => {
+                let listener = TcpListener::bind(("127.0.0.1", *port))?;
+                let c = clients.clone();
+                std::thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        add_client(&c, stream);
+                    }
+                });
+            }
+        }
+        info!("Serving gstore debug events on {:?}", address);
+
+        Ok(Self { clients })
+    }
+
+    pub fn send(&self, event: &DebugEvent) {
+        let line: Arc<str> = match serde_json::to_string(&RemoteEvent::from(event)) {
+            Ok(line) => line.into(),
+            Err(e) => {
+                error!("Could not serialize gstore debug event: {}", e);
+                return;
+            }
+        };
+        let mut clients = match self.clients.lock() {
+            Ok(clients) => clients,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        clients.retain(|client| match client.try_send(line.clone()) {
+            Ok(_) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                warn!("Disconnecting a gstore debug client which does not keep up.");
+                false
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+/// Write the events to the client on its own thread to not block the UI or other clients.
+/// The thread ends when the client disconnects or is dropped from `clients`.
+fn add_client(clients: &Clients, mut stream: impl Write + Send + 'static) {
+    let (send, recv) = mpsc::sync_channel::<Arc<str>>(CLIENT_QUEUE);
+    std::thread::spawn(move || {
+        for line in recv {
+            if writeln!(stream, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+    match clients.lock() {
+        Ok(mut clients) => clients.push(send),
+        Err(poisoned) => poisoned.into_inner().push(send),
+    }
+}
+
+/// Connect to the debug events served by another app instance.
+pub(crate) fn attach(address: &DebugAddress) -> std::io::Result<glib::Receiver<DebugEvent>> {
+    let reader: Box<dyn std::io::Read + Send> = match address {
+        DebugAddress::Unix(path) => Box::new(UnixStream::connect(path)?),
+        DebugAddress::Tcp(port) => Box::new(TcpStream::connect(("127.0.0.1", *port))?),
+    };
+    let (send, recv) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    std::thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    warn!("Lost connection to gstore debug server: {}", e);
+                    break;
+                }
+            };
+            let event = serde_json::from_str::<RemoteEvent>(&line)
+                .map_err(|e| e.to_string())
+                .and_then(|e| e.into_debug_event().map_err(|e| e.to_string()));
+            match event {
+                Ok(event) => {
+                    if send.send(event).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("Ignoring invalid gstore debug event: {}", e),
+            }
+        }
+    });
+    Ok(recv)
+}
@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Async helpers for the common confirm, prompt and error dialogs.
+//!
+//! The dialogs are modal and transient for the active window of the default `gtk::Application`.
+//!
+//! `AdwMessageDialog` requires libadwaita 1.2 (libadwaita-rs 0.2) while this crate depends on
+//! libadwaita-rs 0.1. The dialogs therefore use `gtk::MessageDialog` and style its buttons with
+//! the libadwaita `suggested-action` and `destructive-action` classes.
+//!
+//! ```no_run
+//! # async fn f() {
+//! use gtk_rust_app::dialogs::{self, ResponseAppearance};
+//!
+//! let delete = dialogs::confirm(
+//!     "Delete item?",
+//!     "The item can not be restored.",
+//!     "Delete",
+//!     ResponseAppearance::Destructive,
+//! )
+//! .await;
+//! # }
+//! ```
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+
+/// How the confirming button of a dialog is styled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseAppearance {
+    Default,
+    Suggested,
+    Destructive,
+}
+
+impl ResponseAppearance {
+    fn apply(&self, button: &gtk::Widget) {
+        match self {
+            ResponseAppearance::Default => {}
+            ResponseAppearance::Suggested => button.style_context().add_class("suggested-action"),
+            ResponseAppearance::Destructive => {
+                button.style_context().add_class("destructive-action")
+            }
+        }
+    }
+}
+
+/// The active window of the default application.
+pub fn active_window() -> Option<gtk::Window> {
+    gdk4::gio::Application::default()
+        .and_then(|app| app.downcast::<gtk::Application>().ok())
+        .and_then(|app| app.active_window())
+}
+
+fn dialog(parent: Option<&gtk::Window>, heading: &str, body: &str) -> gtk::MessageDialog {
+    let window = parent.cloned().or_else(active_window);
+    let dialog = gtk::MessageDialog::builder()
+        .modal(true)
+        .text(heading)
+        .secondary_text(body)
+        .build();
+    dialog.set_transient_for(window.as_ref());
+    dialog
+}
+
+/// Ask the user to confirm an action. Returns `true` if the user confirmed.
+pub async fn confirm(
+    heading: &str,
+    body: &str,
+    confirm_label: &str,
+    appearance: ResponseAppearance,
+) -> bool {
+    confirm_for(None, heading, body, confirm_label, appearance).await
+}
+
+/// Like `confirm` but transient for the given window.
+pub async fn confirm_for(
+    parent: Option<&gtk::Window>,
+    heading: &str,
+    body: &str,
+    confirm_label: &str,
+    appearance: ResponseAppearance,
+) -> bool {
+    let dialog = dialog(parent, heading, body);
+    dialog.add_button(&gettext("Cancel"), gtk::ResponseType::Cancel);
+    let button = dialog.add_button(confirm_label, gtk::ResponseType::Accept);
+    appearance.apply(&button);
+    dialog.set_default_response(gtk::ResponseType::Cancel);
+
+    let response = dialog.run_future().await;
+    dialog.close();
+    response == gtk::ResponseType::Accept
+}
+
+/// Ask the user for a text. Returns `None` if the user canceled the dialog.
+pub async fn prompt(heading: &str, body: &str, initial: &str) -> Option<String> {
+    prompt_for(None, heading, body, initial).await
+}
+
+/// Like `prompt` but transient for the given window.
+pub async fn prompt_for(
+    parent: Option<&gtk::Window>,
+    heading: &str,
+    body: &str,
+    initial: &str,
+) -> Option<String> {
+    let dialog = dialog(parent, heading, body);
+    let entry = gtk::Entry::builder()
+        .text(initial)
+        .activates_default(true)
+        .margin_start(12)
+        .margin_end(12)
+        .build();
+    dialog.message_area().append(&entry);
+    dialog.add_button(&gettext("Cancel"), gtk::ResponseType::Cancel);
+    let button = dialog.add_button(&gettext("OK"), gtk::ResponseType::Accept);
+    ResponseAppearance::Suggested.apply(&button);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let response = dialog.run_future().await;
+    let text = entry.text().to_string();
+    dialog.close();
+    (response == gtk::ResponseType::Accept).then(|| text)
+}
+
+/// Show an error message to the user and wait until it was dismissed.
+pub async fn error(heading: &str, body: &str) {
+    error_for(None, heading, body).await
+}
+
+/// Like `error` but transient for the given window.
+pub async fn error_for(parent: Option<&gtk::Window>, heading: &str, body: &str) {
+    let dialog = dialog(parent, heading, body);
+    dialog.set_message_type(gtk::MessageType::Error);
+    dialog.add_button(&gettext("Close"), gtk::ResponseType::Close);
+    dialog.set_default_response(gtk::ResponseType::Close);
+
+    dialog.run_future().await;
+    dialog.close();
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 pub(crate) mod debugging;
+pub mod dialogs;
 pub mod widgets;
 pub mod window;
@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use adw::prelude::ActionRowExt;
+use gdk4::subclass::prelude::ObjectSubclassIsExt;
+use gtk::prelude::*;
+use gtk_rust_app_derive::widget;
+use libadwaita as adw;
+
+use super::layout::page_widget;
+use super::{HeaderWidget, Layout, LayoutBuilder, PageDesc, ToastBuilder, ToastQueue};
+
+/// A layout with a collapsible side panel based on AdwFlap.
+///
+/// ```txt
+/// ┌────────┬──────────────────────┐
+/// │        │ [=]              -xo │
+/// │ Home   ├──────────────────────┤
+/// │        │                      │
+/// │ Page1  │                      │
+/// │        │                      │
+/// │ Page2  │                      │
+/// │        │                      │
+/// └────────┴──────────────────────┘
+/// ```
+///
+/// The side panel can be toggled via the button in the header. On narrow windows
+/// it overlays the content and is hidden after a page was selected.
+#[widget(extends gtk::Box)]
+#[template(file = "flap_layout.xml")]
+pub struct FlapLayout {
+    #[template_child]
+    pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+    #[template_child]
+    pub flap: TemplateChild<adw::Flap>,
+    #[template_child]
+    pub sidebar: TemplateChild<gtk::Box>,
+    #[template_child]
+    pub navigation_sidebar: TemplateChild<gtk::ListBox>,
+    #[template_child]
+    pub header: TemplateChild<adw::HeaderBar>,
+    #[template_child]
+    pub flap_button: TemplateChild<gtk::ToggleButton>,
+    #[template_child]
+    pub view_stack: TemplateChild<adw::ViewStack>,
+
+    pub page_header_widgets: RefCell<HashMap<String, Vec<HeaderWidget>>>,
+    pub toasts: ToastQueue,
+}
+
+impl FlapLayout {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create FlapLayout")
+    }
+
+    pub fn builder() -> LayoutBuilder<Self> {
+        LayoutBuilder::new(Self::new())
+    }
+
+    pub fn constructed(&self) {
+        let s = self;
+        self.imp().view_stack.connect_visible_child_name_notify(
+            glib::clone!(@weak s => move |view_stack| {
+                let name = view_stack.visible_child_name();
+                for (page_name, header_widgets) in s.imp().page_header_widgets.borrow().iter() {
+                    let visible = name.as_deref() == Some(page_name.as_str());
+                    for hw in header_widgets {
+                        hw.widget.set_visible(visible);
+                    }
+                }
+            }),
+        );
+    }
+
+    pub fn get_toast_overlay(&self) -> &adw::ToastOverlay {
+        self.toast_overlay()
+    }
+    pub fn get_flap(&self) -> &adw::Flap {
+        self.flap()
+    }
+    pub fn get_navigation_sidebar(&self) -> &gtk::ListBox {
+        self.navigation_sidebar()
+    }
+    pub fn get_header(&self) -> &adw::HeaderBar {
+        self.header()
+    }
+    pub fn get_view_stack(&self) -> &adw::ViewStack {
+        self.view_stack()
+    }
+}
+
+impl Layout for FlapLayout {
+    fn push_page(&self, page: PageDesc) {
+        let name = page.name.to_string();
+        let visible = self.view_stack().visible_child_name().is_none();
+        for hw in &page.header_widgets {
+            hw.widget.set_visible(visible);
+            hw.pack(self.header());
+        }
+        self.imp()
+            .page_header_widgets
+            .borrow_mut()
+            .insert(name.clone(), page.header_widgets);
+
+        let widget = page_widget(&page);
+        match &page.title_and_icon {
+            Some((title, icon)) => {
+                let stack_page = self
+                    .view_stack()
+                    .add_titled(&widget, Some(page.name), title);
+                stack_page.set_icon_name(Some(icon));
+
+                let row = adw::ActionRow::builder()
+                    .icon_name(icon)
+                    .title(title)
+                    .selectable(true)
+                    .activatable(true)
+                    .name(&name)
+                    .build();
+                let s = self;
+                row.connect_activated(glib::clone!(@weak s => move |_| {
+                    s.show_page(&name);
+                }));
+                self.navigation_sidebar().append(&row);
+            }
+            None => {
+                self.view_stack().add_named(&widget, Some(page.name));
+            }
+        }
+    }
+
+    fn add_header_widget(&self, header_widget: &HeaderWidget) {
+        header_widget.pack(self.header());
+    }
+
+    fn show_page(&self, name: &str) {
+        self.view_stack().set_visible_child_name(name);
+        if self.flap().is_folded() {
+            self.flap().set_reveal_flap(false);
+        }
+    }
+
+    fn toast(&self, toast: ToastBuilder) -> adw::Toast {
+        self.imp().toasts.show(self.toast_overlay(), &toast)
+    }
+}
+
+impl Default for FlapLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::*;
+use libadwaita as adw;
+
+use super::{HeaderWidget, Page, PageDesc, PageStatusContainer, ToastBuilder};
+
+/// The API all app layouts share. Pages written for one layout work with every other layout.
+///
+/// Available layouts are `LeafletLayout`, `TabLayout`, `SinglePageLayout` and `FlapLayout`.
+pub trait Layout: IsA<gtk::Widget> {
+    fn push_page(&self, page: PageDesc);
+    fn add_header_widget(&self, header_widget: &HeaderWidget);
+    fn show_page(&self, name: &str);
+    fn toast(&self, toast: ToastBuilder) -> adw::Toast;
+
+    fn show_message(&self, msg: &str) -> adw::Toast {
+        self.toast(ToastBuilder::new(msg))
+    }
+}
+
+/// The widget a layout adds for the given page. Pages which declare a placeholder are wrapped
+/// in a `PageStatusContainer` whose retry button calls `Page::retry`.
+pub(crate) fn page_widget(page: &PageDesc) -> gtk::Widget {
+    if !page.has_status() {
+        return page.widget.clone();
+    }
+    let container = PageStatusContainer::new(
+        &page.widget,
+        page.loading_status.clone(),
+        page.empty_status.clone(),
+        page.error_status.clone(),
+    );
+    let retry = page.retry.clone();
+    container.connect_retry(move |_| retry());
+    container.upcast()
+}
+
+/// A builder for any `Layout`.
+///
+/// ```no_run
+/// use gtk_rust_app::widgets::{Layout, LayoutBuilder, TabLayout};
+///
+/// let layout: TabLayout = LayoutBuilder::new(TabLayout::new())
+///     // .add_page(pages::my_page())
+///     .build();
+/// ```
+pub struct LayoutBuilder<L: Layout> {
+    layout: L,
+}
+
+impl<L: Layout> LayoutBuilder<L> {
+    pub fn new(layout: L) -> Self {
+        Self { layout }
+    }
+
+    pub fn add_page(self, page: impl Page + IsA<gtk::Widget>) -> Self {
+        self.layout.push_page(PageDesc::new(page));
+        self
+    }
+
+    pub fn add_header_start(self, widget: impl IsA<gtk::Widget>) -> Self {
+        self.layout.add_header_widget(&HeaderWidget::start(widget));
+        self
+    }
+
+    pub fn add_header_end(self, widget: impl IsA<gtk::Widget>) -> Self {
+        self.layout.add_header_widget(&HeaderWidget::end(widget));
+        self
+    }
+
+    pub fn build(self) -> L {
+        self.layout
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 
 use adw::prelude::ActionRowExt;
 use gdk4::{gio::Settings, subclass::prelude::ObjectSubclassIsExt};
@@ -8,47 +9,88 @@ use gtk::prelude::*;
 use gtk_rust_app_derive::widget;
 use libadwaita as adw;
 
-/// A Page descripes a view in the main view stack of the leaflet_layout.
+use super::layout::page_widget;
+use super::{
+    HeaderWidget, Layout, Page, PageDesc, PageRestoreFn, PageSearchFn, PageState,
+    PageStatusContainer, Route, Searchable, ToastBuilder, ToastQueue,
+};
+
+/// A named window width. The active breakpoint of a LeafletLayout is the one with the
+/// smallest `max_width` that is still larger than or equal to the layout width.
 ///
-/// A Page always has an internal name and may define a title and icon.
-pub trait Page {
-    fn name(&self) -> &'static str;
-    fn title_and_icon(&self) -> Option<(String, String)>;
+/// ```no_run
+/// # use gtk_rust_app::widgets::{Breakpoint, ViewSwitcherPosition};
+/// let breakpoints = vec![
+///     Breakpoint::new("phone", 500).fold(),
+///     Breakpoint::new("tablet", 900)
+///         .tablet()
+///         .view_switcher_position(ViewSwitcherPosition::Header),
+/// ];
+/// ```
+#[derive(Debug, Clone)]
+pub struct Breakpoint {
+    pub name: String,
+    pub max_width: i32,
+    /// Fold the leaflet while this breakpoint is active even if the content would fit.
+    pub fold: bool,
+    /// The value of the `tablet` property of the layout while this breakpoint is active.
+    pub tablet: bool,
+    /// Replaces the view switcher position of the layout while this breakpoint is active.
+    pub view_switcher_position: Option<ViewSwitcherPosition>,
 }
 
-#[derive(Debug)]
-pub struct PageDesc {
-    pub widget: gtk::Widget,
-    pub name: &'static str,
-    pub title_and_icon: Option<(String, String)>,
+impl Breakpoint {
+    pub fn new(name: &str, max_width: i32) -> Self {
+        Self {
+            name: name.into(),
+            max_width,
+            fold: false,
+            tablet: false,
+            view_switcher_position: None,
+        }
+    }
+
+    pub fn fold(self) -> Self {
+        let mut s = self;
+        s.fold = true;
+        s
+    }
+
+    pub fn tablet(self) -> Self {
+        let mut s = self;
+        s.tablet = true;
+        s
+    }
+
+    pub fn view_switcher_position(self, position: ViewSwitcherPosition) -> Self {
+        let mut s = self;
+        s.view_switcher_position = Some(position);
+        s
+    }
 }
 
-/// A Header widget describes any widget that is supposed to be shown
-/// in the header bar of the leaflet_layout.
-#[derive(Debug, Clone)]
-pub struct HeaderWidget {
-    widget: gtk::Widget,
-    alignment: HeaderAlignment,
+/// A property value which is set on a widget while a breakpoint is active.
+#[derive(Debug)]
+pub struct BreakpointOverride {
+    pub breakpoint: String,
+    pub widget: glib::WeakRef<glib::Object>,
+    pub property: String,
+    pub value: glib::Value,
+    default: RefCell<Option<glib::Value>>,
 }
 
-/// Describes where to place a HeaderWidget in a gtk HeaderBar.
-#[derive(Debug, Clone)]
-enum HeaderAlignment {
-    Start,
-    End,
+/// Where the view switcher of the leaflet_layout is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewSwitcherPosition {
+    /// A AdwViewSwitcherBar at the bottom which is revealed when the layout is folded.
+    Bottom,
+    /// A AdwViewSwitcherTitle in the main header. It moves to the bottom on narrow windows.
+    Header,
 }
-impl HeaderWidget {
-    pub fn start(widget: impl IsA<gtk::Widget>) -> Self {
-        HeaderWidget {
-            widget: widget.upcast(),
-            alignment: HeaderAlignment::Start,
-        }
-    }
-    pub fn end(widget: impl IsA<gtk::Widget>) -> Self {
-        HeaderWidget {
-            widget: widget.upcast(),
-            alignment: HeaderAlignment::End,
-        }
+
+impl Default for ViewSwitcherPosition {
+    fn default() -> Self {
+        ViewSwitcherPosition::Bottom
     }
 }
 
@@ -78,6 +120,11 @@ impl HeaderWidget {
 ///
 /// - The sidebar is hidden when the window size shrinks to a threashold.
 /// - A AdwViewSwitcherBar appears on the bottom to allow mobile navigation.
+/// - A back button appears in the main header when there is a page to go back to.
+///   `<alt>Left` and the mouse back button navigate back as well.
+/// - An optional search bar passes its query to all `Searchable` pages.
+/// - Detail views can be pushed on top of a page with `push_subpage`. They are shown next
+///   to the page on wide windows and replace it on narrow windows.
 ///
 /// ```txt
 /// ┌───────────┐
@@ -120,13 +167,69 @@ pub struct LeafletLayout {
     #[template_child]
     pub view_switcher_bar: TemplateChild<adw::ViewSwitcherBar>,
     #[template_child]
+    pub content_leaflet: TemplateChild<adw::Leaflet>,
+    #[template_child]
+    pub subpage_stack: TemplateChild<gtk::Stack>,
+    #[template_child]
     pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+    #[template_child]
+    pub back_button: TemplateChild<gtk::Button>,
+    #[template_child]
+    pub search_bar: TemplateChild<gtk::SearchBar>,
+    #[template_child]
+    pub search_entry: TemplateChild<gtk::SearchEntry>,
+    #[template_child]
+    pub search_button: TemplateChild<gtk::ToggleButton>,
+    #[signal_handler(search_entry search_changed)]
+    pub on_search_changed: (),
+    #[signal_handler(back_button clicked)]
+    pub on_back_button_clicked: (),
 
     #[property_bool]
     pub mobile: Cell<bool>,
+    #[property_bool]
+    pub can_go_back: Cell<bool>,
+
+    /// Names of previously visible pages. The last entry is the page `go_back` returns to.
+    pub history: RefCell<Vec<String>>,
+    pub current_page: RefCell<Option<String>>,
+    pub navigating_back: Cell<bool>,
+    /// Set while `reorder_page` re-adds pages. Page changes are not navigations then.
+    pub reordering: Cell<bool>,
+    /// Detail views pushed on top of the visible page via `push_subpage`.
+    pub subpages: RefCell<Vec<(gtk::Widget, String)>>,
+    pub restore_handlers: RefCell<HashMap<String, PageRestoreFn>>,
+    pub pages_model: RefCell<Option<gtk::SelectionModel>>,
+    /// Sidebar section names by page name.
+    pub sections: RefCell<HashMap<String, String>>,
+    /// Main header widgets by page name. Only the widgets of the visible page are shown.
+    pub page_header_widgets: RefCell<HashMap<String, Vec<HeaderWidget>>>,
+    pub search_handlers: RefCell<HashMap<String, PageSearchFn>>,
+    pub search_enabled: Cell<bool>,
+    /// Names of the pages which match the current search query.
+    pub search_matches: RefCell<Vec<String>>,
+    pub toasts: ToastQueue,
+
+    #[property_bool]
+    pub tablet: Cell<bool>,
+    #[property_string]
+    pub breakpoint: Cell<String>,
+    pub breakpoints: RefCell<Vec<Breakpoint>>,
+    pub breakpoint_overrides: RefCell<Vec<BreakpointOverride>>,
+    /// The allocated width. Only tracked while breakpoints are set.
+    pub last_width: Cell<i32>,
+    /// The view switcher position outside of breakpoints which set their own.
+    pub view_switcher_position: Cell<ViewSwitcherPosition>,
+    pub view_switcher_binding: RefCell<Option<glib::Binding>>,
+    /// The title widget of the main header when no subpage is open.
+    pub header_title: RefCell<Option<gtk::Widget>>,
+    /// The URI scheme `open_uri` accepts. Any scheme is accepted if not set.
+    pub uri_scheme: RefCell<Option<String>>,
 
     #[signal]
     adapt: (),
+    #[signal]
+    breakpoint_changed: (),
 }
 
 impl LeafletLayout {
@@ -148,48 +251,835 @@ impl LeafletLayout {
         }
 
         for hw in &sidebar_header_widgets {
-            match hw.alignment {
-                HeaderAlignment::Start => self_.sidebar_header().pack_start(&hw.widget),
-                HeaderAlignment::End => self_.sidebar_header().pack_end(&hw.widget),
-            }
+            hw.pack(self_.sidebar_header());
         }
         for hw in &main_header_widgets {
-            match hw.alignment {
-                HeaderAlignment::Start => self_.main_header().pack_start(&hw.widget),
-                HeaderAlignment::End => self_.main_header().pack_end(&hw.widget),
-            }
+            self_.pack_main_header_widget(hw);
         }
 
         for page in pages {
-            if let Some((title, icon)) = &page.title_and_icon {
-                self_
-                    .view_stack()
-                    .add_titled(&page.widget, Some(page.name), title);
-                let page = self_.view_stack().page(&page.widget);
-                page.set_icon_name(Some(icon));
-            } else {
-                self_.view_stack().add_named(&page.widget, Some(page.name));
-            }
+            self_.add_page_desc(page);
         }
 
-        self_
+        self_.set_fold_threshold_policy(adw::FoldThresholdPolicy::Natural);
+
+        let binding = self_
             .leaflet()
             .bind_property("folded", self_.view_switcher_bar(), "reveal")
             .build();
+        self_.imp().view_switcher_binding.replace(binding);
+
+        self_.update_sidebar();
 
-        append_views_to_sidebar(self_.view_stack(), self_.navigation_sidebar());
+        let pages_model = self_.view_stack().pages();
+        pages_model.connect_items_changed(glib::clone!(@weak self_ => move |_, _, _, _| {
+            self_.update_sidebar();
+        }));
+        // The view stack only holds a weak reference to its pages model.
+        self_.imp().pages_model.replace(Some(pages_model));
 
         self_
     }
 
     pub fn constructed(&self) {
         let s = self;
+        self.imp()
+            .navigation_sidebar
+            .set_header_func(|row, before| {
+                let section = row_section(row);
+                match (section, before) {
+                    (section, Some(before)) if row_section(before) == section => {
+                        row.set_header(gtk::Widget::NONE)
+                    }
+                    (Some(section), _) => {
+                        let label = gtk::Label::builder()
+                            .label(&section)
+                            .xalign(0.0)
+                            .margin_top(12)
+                            .margin_start(12)
+                            .build();
+                        label.style_context().add_class("heading");
+                        label.style_context().add_class("dim-label");
+                        row.set_header(Some(&label));
+                    }
+                    // Separate pages without a section from the section above.
+                    (None, Some(_)) => {
+                        let separator = gtk::Separator::new(gtk::Orientation::Horizontal);
+                        separator.set_margin_top(6);
+                        row.set_header(Some(&separator));
+                    }
+                    (None, None) => row.set_header(gtk::Widget::NONE),
+                }
+            });
         self.imp()
             .leaflet
             .connect_folded_notify(glib::clone!(@weak s => move |l| {
-                s.imp().mobile.set(l.is_folded());
+                s.set_mobile(l.is_folded());
+                s.update_can_go_back();
                 s.emit_adapt()
             }));
+        self.connect_map(|s| {
+            s.set_mobile(s.leaflet().is_folded());
+        });
+        self.imp()
+            .leaflet
+            .connect_visible_child_notify(glib::clone!(@weak s => move |_| {
+                s.update_can_go_back();
+            }));
+        self.imp()
+            .content_leaflet
+            .connect_folded_notify(glib::clone!(@weak s => move |_| {
+                s.update_can_go_back();
+            }));
+        // A back swipe reveals the page without `pop_subpage`. Close the subpages it left.
+        self.imp()
+            .content_leaflet
+            .connect_visible_child_notify(glib::clone!(@weak s => move |l| {
+                if l.visible_child().as_ref() == Some(s.view_stack().upcast_ref())
+                    && !s.imp().subpages.borrow().is_empty()
+                {
+                    s.clear_subpages();
+                }
+            }));
+        self.imp().view_stack.connect_visible_child_name_notify(
+            glib::clone!(@weak s => move |view_stack| {
+                s.on_visible_page_changed(view_stack.visible_child_name().map(|n| n.to_string()));
+            }),
+        );
+
+        self.setup_back_navigation();
+    }
+
+    fn setup_back_navigation(&self) {
+        let s = self;
+
+        let controller = gtk::ShortcutController::new();
+        controller.set_scope(gtk::ShortcutScope::Managed);
+        controller.add_shortcut(&gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string("<alt>Left"),
+            Some(&gtk::CallbackAction::new(
+                glib::clone!(@weak s => @default-return false, move |_, _| {
+                    s.go_back()
+                }),
+            )),
+        ));
+        self.add_controller(&controller);
+
+        // Mouse button 8 is the "back" button on most mice.
+        let gesture = gtk::GestureClick::builder().button(8).build();
+        gesture.connect_pressed(glib::clone!(@weak s => move |gesture, _, _, _| {
+            if s.go_back() {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+            }
+        }));
+        self.add_controller(&gesture);
+    }
+
+    fn on_back_button_clicked(&self, _: gtk::Button) {
+        self.go_back();
+    }
+
+    /// Show the search bar in the main header. It is toggled via `<primary>F` and the search
+    /// button and passes the query to all pages which were added as `Searchable`.
+    pub fn enable_search(&self) {
+        if self.imp().search_enabled.replace(true) {
+            return;
+        }
+        self.search_bar().set_visible(true);
+        self.search_button().set_visible(true);
+        self.search_bar().connect_entry(self.search_entry());
+        self.search_bar().set_key_capture_widget(Some(self));
+        self.search_button()
+            .bind_property("active", self.search_bar(), "search-mode-enabled")
+            .flags(glib::BindingFlags::BIDIRECTIONAL | glib::BindingFlags::SYNC_CREATE)
+            .build();
+
+        let s = self;
+        let controller = gtk::ShortcutController::new();
+        controller.set_scope(gtk::ShortcutScope::Managed);
+        controller.add_shortcut(&gtk::Shortcut::new(
+            gtk::ShortcutTrigger::parse_string("<primary>F"),
+            Some(&gtk::CallbackAction::new(
+                glib::clone!(@weak s => @default-return false, move |_, _| {
+                    let search_bar = s.search_bar();
+                    search_bar.set_search_mode(!search_bar.is_search_mode());
+                    true
+                }),
+            )),
+        ));
+        self.add_controller(&controller);
+    }
+
+    fn on_search_changed(&self, entry: gtk::SearchEntry) {
+        self.search(&entry.text());
+    }
+
+    /// Pass the query to all searchable pages and highlight the matching pages in the sidebar.
+    /// Pages which are not searchable are left as they are.
+    pub fn search(&self, query: &str) {
+        let handlers: Vec<(String, PageSearchFn)> = self
+            .imp()
+            .search_handlers
+            .borrow()
+            .iter()
+            .map(|(name, search)| (name.clone(), search.clone()))
+            .collect();
+        let mut matches: Vec<String> = handlers
+            .into_iter()
+            .filter(|(_, search)| search(query))
+            .map(|(name, _)| name)
+            .collect();
+        if query.is_empty() {
+            matches.clear();
+        }
+        self.imp().search_matches.replace(matches);
+        self.highlight_search_matches();
+    }
+
+    /// Mark the sidebar rows of the pages which match the current search query.
+    fn highlight_search_matches(&self) {
+        let matches = self.imp().search_matches.borrow();
+        let mut c = self.navigation_sidebar().first_child();
+        while let Some(child) = &c {
+            let highlighted = matches.iter().any(|m| *m == child.widget_name());
+            if highlighted {
+                child.style_context().add_class("accent");
+            } else {
+                child.style_context().remove_class("accent");
+            }
+            c = child.next_sibling();
+        }
+    }
+
+    /// Add a menu button with the given menu to the end of the main header.
+    /// See `gtk_rust_app::builder::primary_menu` to generate the menu from the App.toml.
+    pub fn set_primary_menu(&self, menu: &impl IsA<gdk4::gio::MenuModel>) -> gtk::MenuButton {
+        let button = gtk::MenuButton::builder()
+            .icon_name("open-menu-symbolic")
+            .menu_model(menu)
+            .primary(true)
+            .tooltip_text(&gettextrs::gettext("Main Menu"))
+            .build();
+        self.main_header().pack_end(&button);
+        button
+    }
+
+    fn pack_main_header_widget(&self, hw: &HeaderWidget) {
+        hw.pack(self.main_header());
+    }
+
+    fn update_page_header_widgets(&self, visible_page: Option<&str>) {
+        for (name, header_widgets) in self.imp().page_header_widgets.borrow().iter() {
+            let visible = Some(name.as_str()) == visible_page;
+            for hw in header_widgets {
+                hw.widget.set_visible(visible);
+            }
+        }
+    }
+
+    fn on_visible_page_changed(&self, name: Option<String>) {
+        if self.imp().reordering.get() {
+            return;
+        }
+        self.update_page_header_widgets(name.as_deref());
+        let previous = self.imp().current_page.replace(name.clone());
+        if self.imp().navigating_back.get() {
+            return;
+        }
+        self.clear_subpages();
+        if let Some(previous) = previous {
+            if Some(&previous) != name.as_ref() {
+                let mut history = self.imp().history.borrow_mut();
+                // Going back returns to each page once, at its most recent position.
+                history.retain(|n| *n != previous);
+                history.push(previous);
+                if history.len() > MAX_HISTORY {
+                    history.remove(0);
+                }
+            }
+        }
+        self.update_can_go_back();
+    }
+
+    fn update_can_go_back(&self) {
+        let leaflet = self.leaflet();
+        let can_go_back = !self.imp().subpages.borrow().is_empty()
+            || !self.imp().history.borrow().is_empty()
+            || (leaflet.is_folded()
+                && leaflet.visible_child().as_ref() == Some(self.main().upcast_ref()));
+        if can_go_back != self.can_go_back() {
+            self.set_property("can-go-back", can_go_back);
+        }
+        self.back_button().set_visible(can_go_back);
+    }
+
+    /// Whether `go_back` would navigate anywhere.
+    pub fn can_go_back(&self) -> bool {
+        self.property("can-go-back")
+    }
+
+    /// Navigate back to the previously visible page. Open subpages are closed first.
+    /// If there is no page history and the layout is folded, the sidebar is revealed instead.
+    ///
+    /// Returns `true` if a navigation took place.
+    pub fn go_back(&self) -> bool {
+        if self.pop_subpage() {
+            return true;
+        }
+        let previous = self.imp().history.borrow_mut().pop();
+        if let Some(previous) = previous {
+            self.imp().navigating_back.set(true);
+            self.view_stack().set_visible_child_name(&previous);
+            self.imp().navigating_back.set(false);
+            self.update_can_go_back();
+            return true;
+        }
+        if self.leaflet().is_folded() && self.leaflet().navigate(adw::NavigationDirection::Back) {
+            self.update_can_go_back();
+            return true;
+        }
+        false
+    }
+
+    /// Show the page with the given name and remember the current page in the navigation history.
+    pub fn navigate_to(&self, name: &str) {
+        self.view_stack().set_visible_child_name(name);
+        if self.leaflet().is_folded() {
+            self.leaflet().set_visible_child(self.main());
+        }
+    }
+
+    /// Slide in a detail view on top of the visible page, e.g. an item which was opened from a list.
+    ///
+    /// On wide windows the detail view is shown next to the page. On narrow windows it
+    /// replaces the page until the user navigates back.
+    pub fn push_subpage(&self, widget: &impl IsA<gtk::Widget>, title: &str) {
+        let widget = widget.upcast_ref::<gtk::Widget>();
+        self.subpage_stack().add_child(widget);
+        self.subpage_stack().set_visible_child(widget);
+        self.subpage_stack().set_visible(true);
+        self.content_leaflet()
+            .set_visible_child(self.subpage_stack());
+        self.imp()
+            .subpages
+            .borrow_mut()
+            .push((widget.clone(), title.to_string()));
+        self.update_subpage_title();
+        self.update_can_go_back();
+    }
+
+    /// Close the topmost subpage. Returns `false` if there was no subpage to close.
+    pub fn pop_subpage(&self) -> bool {
+        let popped = self.imp().subpages.borrow_mut().pop();
+        if let Some((widget, _)) = popped {
+            let previous = self.imp().subpages.borrow().last().map(|(w, _)| w.clone());
+            match previous {
+                Some(previous) => self.subpage_stack().set_visible_child(&previous),
+                None => {
+                    self.content_leaflet().set_visible_child(self.view_stack());
+                    self.subpage_stack().set_visible(false);
+                }
+            }
+            self.subpage_stack().remove(&widget);
+            self.update_subpage_title();
+            self.update_can_go_back();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn clear_subpages(&self) {
+        while self.pop_subpage() {}
+    }
+
+    fn update_subpage_title(&self) {
+        let title = self.imp().subpages.borrow().last().map(|(_, t)| t.clone());
+        match title {
+            Some(title) => self
+                .main_header()
+                .set_title_widget(Some(&adw::WindowTitle::new(&title, ""))),
+            None => self
+                .main_header()
+                .set_title_widget(self.imp().header_title.borrow().as_ref()),
+        }
+    }
+
+    /// Show the view switcher at the bottom or in the main header. Breakpoints may replace
+    /// the position while they are active.
+    pub fn set_view_switcher_position(&self, position: ViewSwitcherPosition) {
+        self.imp().view_switcher_position.set(position);
+        self.apply_view_switcher_position();
+    }
+
+    fn apply_view_switcher_position(&self) {
+        let position = self
+            .active_breakpoint_desc()
+            .and_then(|b| b.view_switcher_position)
+            .unwrap_or_else(|| self.imp().view_switcher_position.get());
+        let current = match self.imp().header_title.borrow().is_some() {
+            true => ViewSwitcherPosition::Header,
+            false => ViewSwitcherPosition::Bottom,
+        };
+        if position == current && self.imp().view_switcher_binding.borrow().is_some() {
+            return;
+        }
+        if let Some(binding) = self.imp().view_switcher_binding.take() {
+            binding.unbind();
+        }
+        match position {
+            ViewSwitcherPosition::Bottom => {
+                let binding = self
+                    .leaflet()
+                    .bind_property("folded", self.view_switcher_bar(), "reveal")
+                    .flags(glib::BindingFlags::SYNC_CREATE)
+                    .build();
+                self.imp().view_switcher_binding.replace(binding);
+                self.imp().header_title.replace(None);
+            }
+            ViewSwitcherPosition::Header => {
+                let title = adw::ViewSwitcherTitle::builder()
+                    .stack(self.view_stack())
+                    .build();
+                let binding = title
+                    .bind_property("title-visible", self.view_switcher_bar(), "reveal")
+                    .flags(glib::BindingFlags::SYNC_CREATE)
+                    .build();
+                self.imp().view_switcher_binding.replace(binding);
+                self.imp().header_title.replace(Some(title.upcast()));
+            }
+        }
+        self.update_subpage_title();
+    }
+
+    pub fn set_fold_threshold_policy(&self, policy: adw::FoldThresholdPolicy) {
+        self.leaflet().set_fold_threshold_policy(policy);
+    }
+
+    /// Set the named breakpoints of this layout. See `Breakpoint`.
+    pub fn set_breakpoints(&self, breakpoints: Vec<Breakpoint>) {
+        let mut breakpoints = breakpoints;
+        breakpoints.sort_by_key(|b| b.max_width);
+        let watch = !breakpoints.is_empty();
+        self.imp().breakpoints.replace(breakpoints);
+        if watch {
+            self.watch_width();
+        }
+        self.update_breakpoint(self.width());
+    }
+
+    /// GTK4 has no size-allocate signal. Allocate the single child with a custom layout
+    /// manager to learn about width changes.
+    fn watch_width(&self) {
+        if self
+            .layout_manager()
+            .map_or(false, |l| l.is::<gtk::CustomLayout>())
+        {
+            return;
+        }
+        let layout = gtk::CustomLayout::new(
+            Some(|widget: &gtk::Widget| {
+                widget
+                    .first_child()
+                    .map(|c| c.request_mode())
+                    .unwrap_or(gtk::SizeRequestMode::ConstantSize)
+            }),
+            |widget, orientation, for_size| {
+                widget
+                    .first_child()
+                    .map(|c| c.measure(orientation, for_size))
+                    .unwrap_or((0, 0, -1, -1))
+            },
+            |widget, width, height, baseline| {
+                if let Some(child) = widget.first_child() {
+                    child.allocate(width, height, baseline, None);
+                }
+                let s = match widget.downcast_ref::<LeafletLayout>() {
+                    Some(s) => s,
+                    None => return,
+                };
+                if width != s.imp().last_width.replace(width) {
+                    // Changing the fold or header while allocating would queue another resize.
+                    glib::idle_add_local_once(glib::clone!(@weak s => move || {
+                        s.update_breakpoint(s.imp().last_width.get());
+                    }));
+                }
+            },
+        );
+        self.set_layout_manager(Some(&layout));
+    }
+
+    /// Set `property` of `widget` to `value` while the breakpoint with the given name is active.
+    /// The previous value is restored when the breakpoint becomes inactive.
+    pub fn add_breakpoint_override(
+        &self,
+        breakpoint: &str,
+        widget: &impl IsA<glib::Object>,
+        property: &str,
+        value: impl ToValue,
+    ) {
+        self.imp()
+            .breakpoint_overrides
+            .borrow_mut()
+            .push(BreakpointOverride {
+                breakpoint: breakpoint.into(),
+                widget: widget.upcast_ref::<glib::Object>().downgrade(),
+                property: property.into(),
+                value: value.to_value(),
+                default: RefCell::new(None),
+            });
+        self.apply_breakpoint_overrides();
+    }
+
+    /// The name of the active breakpoint or an empty string.
+    pub fn active_breakpoint(&self) -> String {
+        self.property("breakpoint")
+    }
+
+    /// Whether the active breakpoint is marked with `Breakpoint::tablet`.
+    pub fn is_tablet(&self) -> bool {
+        self.property("tablet")
+    }
+
+    pub fn connect_breakpoint_changed(&self, f: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self._connect_breakpoint_changed(f)
+    }
+
+    fn active_breakpoint_desc(&self) -> Option<Breakpoint> {
+        let active = self.active_breakpoint();
+        self.imp()
+            .breakpoints
+            .borrow()
+            .iter()
+            .find(|b| b.name == active)
+            .cloned()
+    }
+
+    fn update_breakpoint(&self, width: i32) {
+        let breakpoint = self
+            .imp()
+            .breakpoints
+            .borrow()
+            .iter()
+            .find(|b| width <= b.max_width)
+            .cloned();
+        let name = breakpoint
+            .as_ref()
+            .map(|b| b.name.clone())
+            .unwrap_or_default();
+        if name == self.active_breakpoint() {
+            return;
+        }
+        let fold = breakpoint.as_ref().map_or(false, |b| b.fold);
+        self.leaflet().set_can_unfold(!fold);
+        self.set_property("tablet", breakpoint.as_ref().map_or(false, |b| b.tablet));
+        self.set_property("breakpoint", name);
+        self.apply_view_switcher_position();
+        self.apply_breakpoint_overrides();
+        self.emit_breakpoint_changed();
+    }
+
+    fn apply_breakpoint_overrides(&self) {
+        let active = self.active_breakpoint();
+        for o in self.imp().breakpoint_overrides.borrow().iter() {
+            let widget = match o.widget.upgrade() {
+                Some(widget) => widget,
+                None => continue,
+            };
+            if o.breakpoint == active {
+                if o.default.borrow().is_none() {
+                    o.default.replace(Some(widget.property_value(&o.property)));
+                }
+                widget.set_property_from_value(&o.property, &o.value);
+            } else if let Some(default) = o.default.take() {
+                widget.set_property_from_value(&o.property, &default);
+            }
+        }
+    }
+
+    /// Add a page after the layout was built. The sidebar and view switcher are updated automatically.
+    pub fn add_page(&self, page: impl Page + IsA<gtk::Widget>) {
+        self.add_page_desc(PageDesc::new(page));
+    }
+
+    /// Add a searchable page after the layout was built.
+    pub fn add_searchable_page(&self, page: impl Page + Searchable + IsA<gtk::Widget>) {
+        self.add_page_desc(PageDesc::searchable(page));
+    }
+
+    fn add_page_desc(&self, page: PageDesc) {
+        self.imp()
+            .restore_handlers
+            .borrow_mut()
+            .insert(page.name.to_string(), page.restore.clone());
+        if let Some(search) = &page.search {
+            self.imp()
+                .search_handlers
+                .borrow_mut()
+                .insert(page.name.to_string(), search.clone());
+        }
+        if let Some(section) = &page.section {
+            self.imp()
+                .sections
+                .borrow_mut()
+                .insert(page.name.to_string(), section.clone());
+        }
+        if !page.header_widgets.is_empty() {
+            let visible = self.view_stack().visible_child_name().as_deref() == Some(page.name);
+            for hw in &page.header_widgets {
+                hw.widget.set_visible(visible);
+                self.pack_main_header_widget(hw);
+            }
+            self.imp()
+                .page_header_widgets
+                .borrow_mut()
+                .insert(page.name.to_string(), page.header_widgets);
+        }
+        let widget = page_widget(&page);
+        let stack_page = if let Some((title, icon)) = &page.title_and_icon {
+            let stack_page = self
+                .view_stack()
+                .add_titled(&widget, Some(page.name), title);
+            stack_page.set_icon_name(Some(icon));
+            stack_page
+        } else {
+            self.view_stack().add_named(&widget, Some(page.name))
+        };
+        stack_page.set_badge_number(page.badge_number);
+        stack_page.set_needs_attention(page.needs_attention);
+        let s = self;
+        stack_page.connect_visible_notify(glib::clone!(@weak s => move |_| {
+            s.update_sidebar();
+        }));
+    }
+
+    /// Remove the page with the given name. Returns `false` if there is no such page.
+    pub fn remove_page(&self, name: &str) -> bool {
+        match self.view_stack().child_by_name(name) {
+            Some(widget) => {
+                self.view_stack().remove(&widget);
+                self.imp().restore_handlers.borrow_mut().remove(name);
+                self.imp().sections.borrow_mut().remove(name);
+                self.imp().search_handlers.borrow_mut().remove(name);
+                if let Some(header_widgets) =
+                    self.imp().page_header_widgets.borrow_mut().remove(name)
+                {
+                    for hw in header_widgets {
+                        self.main_header().remove(&hw.widget);
+                    }
+                }
+                self.imp().history.borrow_mut().retain(|n| n != name);
+                self.update_can_go_back();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Show or hide the page with the given name in the sidebar and view switcher.
+    pub fn set_page_visible(&self, name: &str, visible: bool) {
+        if let Some(widget) = self.view_stack().child_by_name(name) {
+            self.view_stack().page(&widget).set_visible(visible);
+        }
+    }
+
+    /// Switch the page with the given name to the given state. See `Page::loading_status`.
+    pub fn set_page_state(&self, name: &str, state: PageState) {
+        match self
+            .view_stack()
+            .child_by_name(name)
+            .and_then(|w| w.downcast::<PageStatusContainer>().ok())
+        {
+            Some(container) => container.set_state(state),
+            None => warn!("Page '{}' does not declare any placeholder.", name),
+        }
+    }
+
+    /// Update the badge shown next to the page in the sidebar and view switcher. `0` hides the badge.
+    pub fn set_page_badge_number(&self, name: &str, badge_number: u32) {
+        if let Some(widget) = self.view_stack().child_by_name(name) {
+            self.view_stack()
+                .page(&widget)
+                .set_badge_number(badge_number);
+        }
+    }
+
+    /// Mark the page as needing attention in the sidebar and view switcher.
+    pub fn set_page_needs_attention(&self, name: &str, needs_attention: bool) {
+        if let Some(widget) = self.view_stack().child_by_name(name) {
+            self.view_stack()
+                .page(&widget)
+                .set_needs_attention(needs_attention);
+        }
+    }
+
+    /// Move the page with the given name to `position`.
+    pub fn reorder_page(&self, name: &str, position: u32) {
+        let widget = match self.view_stack().child_by_name(name) {
+            Some(widget) => widget,
+            None => return,
+        };
+        let model = self.view_stack().pages();
+        let mut stack_pages: Vec<adw::ViewStackPage> = (0..model.n_items())
+            .filter_map(|i| model.item(i).and_then(|o| o.downcast().ok()))
+            .collect();
+        let index = match stack_pages.iter().position(|p| p.child() == widget) {
+            Some(index) => index,
+            None => return,
+        };
+        let stack_page = stack_pages.remove(index);
+        let position = (position as usize).min(stack_pages.len());
+        stack_pages.insert(position, stack_page);
+
+        // AdwViewStack can only append pages. Re-add every page after the new position.
+        let visible = self.view_stack().visible_child_name();
+        let s = self;
+        self.imp().reordering.set(true);
+        for stack_page in stack_pages.iter().skip(position.min(index)) {
+            let child = stack_page.child();
+            let name = stack_page.name();
+            let title = stack_page.title();
+            let icon_name = stack_page.icon_name();
+            let page_visible = stack_page.is_visible();
+            let badge_number = stack_page.badge_number();
+            let needs_attention = stack_page.needs_attention();
+            self.view_stack().remove(&child);
+            let new_page = match &title {
+                Some(title) => self.view_stack().add_titled(&child, name.as_deref(), title),
+                None => self.view_stack().add_named(&child, name.as_deref()),
+            };
+            new_page.set_icon_name(icon_name.as_deref());
+            new_page.set_visible(page_visible);
+            new_page.set_badge_number(badge_number);
+            new_page.set_needs_attention(needs_attention);
+            new_page.connect_visible_notify(glib::clone!(@weak s => move |_| {
+                s.update_sidebar();
+            }));
+        }
+        if let Some(visible) = visible {
+            self.view_stack().set_visible_child_name(&visible);
+        }
+        self.imp().reordering.set(false);
+        self.update_sidebar();
+    }
+
+    fn update_sidebar(&self) {
+        // `reorder_page` updates the sidebar once it is done.
+        if self.imp().reordering.get() {
+            return;
+        }
+        let navigation_sidebar = self.navigation_sidebar();
+        while let Some(child) = navigation_sidebar.first_child() {
+            navigation_sidebar.remove(&child);
+        }
+        append_views_to_sidebar(self);
+        self.highlight_search_matches();
+    }
+
+    /// Show the page a deep link like `myapp://page/settings?id=42` points to and
+    /// pass the query parameters to `Page::restore`.
+    ///
+    /// Returns `false` if the URI could not be parsed, has a foreign scheme (see `handle_uris`)
+    /// or the page does not exist.
+    pub fn open_uri(&self, uri: &str) -> bool {
+        match Route::parse(uri) {
+            Some(route) => {
+                if let Some(scheme) = self.imp().uri_scheme.borrow().as_ref() {
+                    if !route.scheme.eq_ignore_ascii_case(scheme) {
+                        warn!("Ignoring URI '{}' with a foreign scheme", uri);
+                        return false;
+                    }
+                }
+                self.open_route(&route)
+            }
+            None => {
+                warn!("Could not route URI '{}'", uri);
+                false
+            }
+        }
+    }
+
+    pub fn open_route(&self, route: &Route) -> bool {
+        if self.view_stack().child_by_name(&route.page).is_none() {
+            warn!("Could not route to unknown page '{}'", route.page);
+            return false;
+        }
+        self.navigate_to(&route.page);
+        let restore = self
+            .imp()
+            .restore_handlers
+            .borrow()
+            .get(&route.page)
+            .cloned();
+        if let Some(restore) = restore {
+            restore(&route.params);
+        }
+        true
+    }
+
+    /// Register the `app.open-uri` action which routes its string argument via `open_uri`.
+    /// Only URIs with the given scheme (e.g. `myapp`) are routed.
+    ///
+    /// URIs from the command line or D-Bus `Open` are delegated to this action when
+    /// the app was built with `AppBuilder::handle_uris`. Notifications may use it as
+    /// their default action as well.
+    pub fn handle_uris(&self, application: &gtk::Application, scheme: &str) {
+        self.imp().uri_scheme.replace(Some(scheme.to_string()));
+        let action = gdk4::gio::SimpleAction::new("open-uri", Some(glib::VariantTy::STRING));
+        let s = self;
+        action.connect_activate(glib::clone!(@weak s => move |_, argument| {
+            if let Some(uri) = argument.and_then(|a| a.get::<String>()) {
+                s.open_uri(&uri);
+            }
+        }));
+        application.add_action(&action);
+    }
+
+    /// Show the page stored in the `last-page` setting and keep the setting updated
+    /// with the visible page. If the stored page does not exist (anymore) the first
+    /// page stays visible.
+    ///
+    /// The app needs to define the setting in its App.toml:
+    ///
+    /// ```toml
+    /// [settings]
+    /// last-page = ""
+    /// ```
+    pub fn bind_last_page(&self, settings: &Settings) {
+        let has_key = settings
+            .settings_schema()
+            .map(|schema| schema.has_key("last-page"))
+            .unwrap_or(false);
+        if !has_key {
+            warn!("Can not remember the last page: Setting 'last-page' is not defined.");
+            return;
+        }
+
+        let last_page = settings.string("last-page");
+        if !last_page.is_empty() && self.view_stack().child_by_name(&last_page).is_some() {
+            self.view_stack().set_visible_child_name(&last_page);
+            // Restoring the last page is not a navigation the user should go back from.
+            self.imp().history.borrow_mut().clear();
+            self.update_can_go_back();
+        }
+
+        let s = self;
+        self.view_stack().connect_visible_child_name_notify(
+            glib::clone!(@weak s, @strong settings => move |view_stack| {
+                if s.imp().reordering.get() {
+                    return;
+                }
+                if let Some(name) = view_stack.visible_child_name() {
+                    if let Err(e) = settings.set_string("last-page", &name) {
+                        error!("Could not store last page: {}", e);
+                    }
+                }
+            }),
+        );
+    }
+
+    pub fn connect_can_go_back_notify(&self, f: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self.connect_notify_local(Some("can-go-back"), move |s, _| f(s))
     }
 
     pub fn get_leaflet(&self) -> &adw::Leaflet {
@@ -225,11 +1115,35 @@ impl LeafletLayout {
     pub fn get_toast_overlay(&self) -> &adw::ToastOverlay {
         self.toast_overlay()
     }
+    pub fn get_back_button(&self) -> &gtk::Button {
+        self.back_button()
+    }
+    pub fn get_search_bar(&self) -> &gtk::SearchBar {
+        self.search_bar()
+    }
+    pub fn get_search_entry(&self) -> &gtk::SearchEntry {
+        self.search_entry()
+    }
+    pub fn get_content_leaflet(&self) -> &adw::Leaflet {
+        self.content_leaflet()
+    }
+    pub fn get_subpage_stack(&self) -> &gtk::Stack {
+        self.subpage_stack()
+    }
 
+    /// Whether the layout is folded. This is also available as notifying `mobile` property.
     pub fn is_mobile(&self) -> bool {
-        let mobile = self.imp().mobile.take();
-        self.imp().mobile.set(mobile);
-        mobile
+        self.property("mobile")
+    }
+
+    fn set_mobile(&self, mobile: bool) {
+        if mobile != self.is_mobile() {
+            self.set_property("mobile", mobile);
+        }
+    }
+
+    pub fn connect_mobile_notify(&self, f: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self.connect_notify_local(Some("mobile"), move |s, _| f(s))
     }
 
     pub fn connect_adapt(&self, f: impl Fn(&Self) + 'static) {
@@ -246,32 +1160,184 @@ impl LeafletLayout {
         self.toast_overlay().add_toast(toast);
     }
 
+    /// Show a toast with an optional action button, timeout, priority and key.
+    /// Earlier toasts with the same key are dismissed.
+    pub fn toast(&self, toast: ToastBuilder) -> adw::Toast {
+        self.imp().toasts.show(self.toast_overlay(), &toast)
+    }
+
+    fn window(&self) -> Option<gtk::Window> {
+        self.root().and_then(|r| r.downcast::<gtk::Window>().ok())
+    }
+
+    /// See `gtk_rust_app::dialogs::confirm`.
+    pub async fn confirm(
+        &self,
+        heading: &str,
+        body: &str,
+        confirm_label: &str,
+        appearance: crate::dialogs::ResponseAppearance,
+    ) -> bool {
+        crate::dialogs::confirm_for(
+            self.window().as_ref(),
+            heading,
+            body,
+            confirm_label,
+            appearance,
+        )
+        .await
+    }
+
+    /// See `gtk_rust_app::dialogs::prompt`.
+    pub async fn prompt(&self, heading: &str, body: &str, initial: &str) -> Option<String> {
+        crate::dialogs::prompt_for(self.window().as_ref(), heading, body, initial).await
+    }
+
+    /// See `gtk_rust_app::dialogs::error`.
+    pub async fn error(&self, heading: &str, body: &str) {
+        crate::dialogs::error_for(self.window().as_ref(), heading, body).await
+    }
+
+    /// Dismiss the toast which was shown with the given key.
+    pub fn dismiss_toast(&self, key: &str) {
+        self.imp().toasts.dismiss(key);
+    }
+
+    /// Register the `app.show-toast` action. It expects an `a{sv}` argument as
+    /// created by `ToastBuilder::to_variant` and allows to show toasts without a reference
+    /// to the layout, e.g. from gstore reducers:
+    ///
+    /// ```no_run
+    /// # fn f(app: &gtk::Application) {
+    /// use gtk::prelude::*;
+    /// use gtk_rust_app::widgets::ToastBuilder;
+    ///
+    /// app.activate_action("show-toast", Some(&ToastBuilder::new("Saved").to_variant()));
+    /// # }
+    /// ```
+    pub fn handle_toast_action(&self, application: &gtk::Application) {
+        let action = gdk4::gio::SimpleAction::new("show-toast", Some(glib::VariantTy::VARDICT));
+        let s = self;
+        action.connect_activate(glib::clone!(@weak s => move |_, argument| {
+            match argument.and_then(ToastBuilder::from_variant) {
+                Some(toast) => {
+                    s.toast(toast);
+                }
+                None => warn!("Invalid argument for app.show-toast: {:?}", argument),
+            }
+        }));
+        application.add_action(&action);
+    }
+
     pub fn builder(settings: Option<&Settings>) -> LeafletLayoutBuilder {
         LeafletLayoutBuilder::new(settings)
     }
 }
 
-fn append_views_to_sidebar(view_stack: &adw::ViewStack, navigation_sidebar: &gtk::ListBox) {
+impl Layout for LeafletLayout {
+    fn push_page(&self, page: PageDesc) {
+        self.add_page_desc(page);
+    }
+
+    fn add_header_widget(&self, header_widget: &HeaderWidget) {
+        self.pack_main_header_widget(header_widget);
+    }
+
+    fn show_page(&self, name: &str) {
+        self.navigate_to(name);
+    }
+
+    fn toast(&self, toast: ToastBuilder) -> adw::Toast {
+        LeafletLayout::toast(self, toast)
+    }
+
+    fn show_message(&self, msg: &str) -> adw::Toast {
+        LeafletLayout::show_message(self, msg)
+    }
+}
+
+/// The number of previously visible pages `go_back` can return to.
+const MAX_HISTORY: usize = 50;
+
+const ROW_SECTION_KEY: &str = "gra-sidebar-section";
+
+fn set_row_section(row: &impl IsA<gtk::ListBoxRow>, section: Option<String>) {
+    // SAFETY: The key is only ever used with `Option<String>` values.
+    unsafe { row.set_data(ROW_SECTION_KEY, section) }
+}
+
+fn row_section(row: &impl IsA<gtk::ListBoxRow>) -> Option<String> {
+    // SAFETY: The key is only ever used with `Option<String>` values.
+    unsafe {
+        row.data::<Option<String>>(ROW_SECTION_KEY)
+            .and_then(|s| s.as_ref().clone())
+    }
+}
+
+fn append_views_to_sidebar(layout: &LeafletLayout) {
+    let view_stack = layout.view_stack();
+    let navigation_sidebar = layout.navigation_sidebar();
     let model = view_stack.pages();
-    for i in 0..model.n_items() {
-        let o = model.item(i).unwrap();
-        let page: adw::ViewStackPage = o.downcast().unwrap();
+    let mut pages: Vec<adw::ViewStackPage> = (0..model.n_items())
+        .map(|i| model.item(i).unwrap().downcast().unwrap())
+        .collect();
+
+    // Group pages by section, sections are ordered by their first page.
+    let sections = layout.imp().sections.borrow().clone();
+    let mut section_order: Vec<Option<&String>> = Vec::new();
+    for page in &pages {
+        let section = page.name().and_then(|n| sections.get(n.as_str()));
+        if !section_order.contains(&section) {
+            section_order.push(section);
+        }
+    }
+    pages.sort_by_key(|page| {
+        let section = page.name().and_then(|n| sections.get(n.as_str()));
+        section_order.iter().position(|s| *s == section)
+    });
 
+    for page in pages {
         let name = page
             .name()
             .map(|n| n.to_string())
             .unwrap_or_else(|| "".into());
 
-        if page.title().is_some() {
+        if page.title().is_some() && page.is_visible() {
             let row = adw::ActionRow::builder()
                 .icon_name(&page.icon_name().unwrap_or_else(|| "".into()))
                 .title(&page.title().unwrap_or_else(|| "".into()))
                 .selectable(true)
                 .activatable(true)
+                .name(&name)
+                .build();
+            set_row_section(&row, sections.get(&name).cloned());
+
+            let badge = gtk::Label::builder().valign(gtk::Align::Center).build();
+            badge.style_context().add_class("dim-label");
+            badge.style_context().add_class("numeric");
+            page.bind_property("badge-number", &badge, "label")
+                .transform_to(|_, v| Some(v.get::<u32>().unwrap().to_string().to_value()))
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
+            page.bind_property("badge-number", &badge, "visible")
+                .transform_to(|_, v| Some((v.get::<u32>().unwrap() > 0).to_value()))
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
+            row.add_suffix(&badge);
+
+            let attention = gtk::Image::builder()
+                .icon_name("media-record-symbolic")
+                .pixel_size(8)
+                .valign(gtk::Align::Center)
                 .build();
+            attention.style_context().add_class("accent");
+            page.bind_property("needs-attention", &attention, "visible")
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
+            row.add_suffix(&attention);
 
-            row.connect_activated(glib::clone!( @weak view_stack => move |_| {
-                view_stack.set_visible_child_name(&name)
+            row.connect_activated(glib::clone!( @weak layout => move |_| {
+                layout.navigate_to(&name)
             }));
 
             navigation_sidebar.append(&row);
@@ -284,6 +1350,12 @@ pub struct LeafletLayoutBuilder<'a> {
     sidebar_header_widgets: Vec<HeaderWidget>,
     main_header_widgets: Vec<HeaderWidget>,
     pages: Vec<PageDesc>,
+    remember_last_page: bool,
+    search: bool,
+    primary_menu: Option<gdk4::gio::MenuModel>,
+    breakpoints: Vec<Breakpoint>,
+    fold_threshold_policy: Option<adw::FoldThresholdPolicy>,
+    view_switcher_position: ViewSwitcherPosition,
 }
 impl<'a> LeafletLayoutBuilder<'a> {
     pub fn new(settings: Option<&'a Settings>) -> Self {
@@ -292,46 +1364,127 @@ impl<'a> LeafletLayoutBuilder<'a> {
             sidebar_header_widgets: Vec::new(),
             main_header_widgets: Vec::new(),
             pages: Vec::new(),
+            remember_last_page: false,
+            search: false,
+            primary_menu: None,
+            breakpoints: Vec::new(),
+            fold_threshold_policy: None,
+            view_switcher_position: ViewSwitcherPosition::Bottom,
         }
     }
 
+    /// Reopen the page which was visible when the app was closed. Requires a `last-page`
+    /// setting (see `LeafletLayout::bind_last_page`).
+    pub fn remember_last_page(self) -> Self {
+        let mut s = self;
+        s.remember_last_page = true;
+        s
+    }
+
     pub fn add_page(self, page: impl Page + IsA<gtk::Widget>) -> Self {
         let mut s = self;
-        let name = page.name();
-        let title_and_icon = page.title_and_icon();
-        let page_desc = PageDesc {
-            widget: page.upcast(),
-            name,
-            title_and_icon,
-        };
-        s.pages.push(page_desc);
+        s.pages.push(PageDesc::new(page));
+        s
+    }
+
+    /// Add a page which receives the query of the search bar. Enables the search bar.
+    pub fn add_searchable_page(self, page: impl Page + Searchable + IsA<gtk::Widget>) -> Self {
+        let mut s = self;
+        s.pages.push(PageDesc::searchable(page));
+        s.search = true;
+        s
+    }
+
+    /// Show a menu button with the given menu in the main header.
+    pub fn primary_menu(self, menu: &impl IsA<gdk4::gio::MenuModel>) -> Self {
+        let mut s = self;
+        s.primary_menu = Some(menu.clone().upcast());
+        s
+    }
+
+    /// Add a named breakpoint. See `Breakpoint`.
+    pub fn breakpoint(self, name: &str, max_width: i32) -> Self {
+        self.add_breakpoint(Breakpoint::new(name, max_width))
+    }
+
+    /// Add a breakpoint which folds the leaflet, sets `tablet` or moves the view switcher.
+    pub fn add_breakpoint(self, breakpoint: Breakpoint) -> Self {
+        let mut s = self;
+        s.breakpoints.push(breakpoint);
+        s
+    }
+
+    /// Defaults to `FoldThresholdPolicy::Natural`.
+    pub fn fold_threshold_policy(self, policy: adw::FoldThresholdPolicy) -> Self {
+        let mut s = self;
+        s.fold_threshold_policy = Some(policy);
+        s
+    }
+
+    pub fn view_switcher_position(self, position: ViewSwitcherPosition) -> Self {
+        let mut s = self;
+        s.view_switcher_position = position;
+        s
+    }
+
+    /// Show a search bar below the main header.
+    pub fn search(self) -> Self {
+        let mut s = self;
+        s.search = true;
+        s
+    }
+
+    pub fn add_sidebar_header_start(self, widget: impl IsA<gtk::Widget>) -> Self {
+        let mut s = self;
+        s.sidebar_header_widgets.push(HeaderWidget::start(widget));
+        s
+    }
+
+    pub fn add_sidebar_header_end(self, widget: impl IsA<gtk::Widget>) -> Self {
+        let mut s = self;
+        s.sidebar_header_widgets.push(HeaderWidget::end(widget));
         s
     }
 
     pub fn add_main_header_start(self, widget: impl IsA<gtk::Widget>) -> Self {
         let mut s = self;
-        s.main_header_widgets.push(HeaderWidget {
-            widget: widget.upcast(),
-            alignment: HeaderAlignment::Start,
-        });
+        s.main_header_widgets.push(HeaderWidget::start(widget));
         s
     }
 
     pub fn add_main_header_end(self, widget: impl IsA<gtk::Widget>) -> Self {
         let mut s = self;
-        s.main_header_widgets.push(HeaderWidget {
-            widget: widget.upcast(),
-            alignment: HeaderAlignment::End,
-        });
+        s.main_header_widgets.push(HeaderWidget::end(widget));
         s
     }
 
     pub fn build(self) -> LeafletLayout {
-        LeafletLayout::new(
+        let layout = LeafletLayout::new(
             self.settings,
             self.sidebar_header_widgets,
             self.main_header_widgets,
             self.pages,
-        )
+        );
+        if !self.breakpoints.is_empty() {
+            layout.set_breakpoints(self.breakpoints);
+        }
+        if let Some(policy) = self.fold_threshold_policy {
+            layout.set_fold_threshold_policy(policy);
+        }
+        if self.view_switcher_position != ViewSwitcherPosition::Bottom {
+            layout.set_view_switcher_position(self.view_switcher_position);
+        }
+        if let Some(menu) = &self.primary_menu {
+            layout.set_primary_menu(menu);
+        }
+        if self.search {
+            layout.enable_search();
+        }
+        if self.remember_last_page {
+            if let Some(settings) = self.settings {
+                layout.bind_last_page(settings);
+            }
+        }
+        layout
     }
 }
@@ -1,11 +1,43 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+#[cfg(feature = "libadwaita")]
+mod flap_layout;
+#[cfg(feature = "libadwaita")]
+mod layout;
 #[cfg(feature = "libadwaita")]
 mod leaflet_layout;
 #[cfg(feature = "libadwaita")]
+mod page;
+#[cfg(feature = "libadwaita")]
+mod page_status;
+#[cfg(feature = "libadwaita")]
+mod route;
+#[cfg(feature = "libadwaita")]
 mod sidebar;
+#[cfg(feature = "libadwaita")]
+mod single_page_layout;
+#[cfg(feature = "libadwaita")]
+mod tab_layout;
+#[cfg(feature = "libadwaita")]
+mod toast;
 
+#[cfg(feature = "libadwaita")]
+pub use flap_layout::*;
+#[cfg(feature = "libadwaita")]
+pub use layout::*;
 #[cfg(feature = "libadwaita")]
 pub use leaflet_layout::*;
 #[cfg(feature = "libadwaita")]
+pub use page::*;
+#[cfg(feature = "libadwaita")]
+pub use page_status::*;
+#[cfg(feature = "libadwaita")]
+pub use route::*;
+#[cfg(feature = "libadwaita")]
 pub use sidebar::*;
+#[cfg(feature = "libadwaita")]
+pub use single_page_layout::*;
+#[cfg(feature = "libadwaita")]
+pub use tab_layout::*;
+#[cfg(feature = "libadwaita")]
+pub use toast::*;
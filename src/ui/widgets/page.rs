@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use libadwaita as adw;
+
+use super::StatusDesc;
+
+/// A Page descripes a view in the main view stack of a layout like the leaflet_layout.
+///
+/// A Page always has an internal name and may define a title and icon.
+pub trait Page {
+    fn name(&self) -> &'static str;
+    fn title_and_icon(&self) -> Option<(String, String)>;
+
+    /// Called when the page is opened via a deep link (see `LeafletLayout::open_uri`)
+    /// with the query parameters of the link.
+    fn restore(&self, _params: &HashMap<String, String>) {}
+
+    /// The name of the sidebar section this page is grouped in.
+    fn section(&self) -> Option<String> {
+        None
+    }
+
+    /// The initial badge number of this page. Use `LeafletLayout::set_page_badge_number` to update it.
+    fn badge_number(&self) -> u32 {
+        0
+    }
+
+    /// Whether the page initially needs attention. Use `LeafletLayout::set_page_needs_attention` to update it.
+    fn needs_attention(&self) -> bool {
+        false
+    }
+
+    /// Widgets which are shown in the main header bar only while this page is visible.
+    fn header_widgets(&self) -> Vec<HeaderWidget> {
+        Vec::new()
+    }
+
+    /// The placeholder which is shown while the page is in the `PageState::Loading` state.
+    /// Pages which declare any placeholder can switch their state via `set_page_state`.
+    fn loading_status(&self) -> Option<StatusDesc> {
+        None
+    }
+
+    /// The placeholder which is shown while the page is in the `PageState::Empty` state.
+    fn empty_status(&self) -> Option<StatusDesc> {
+        None
+    }
+
+    /// The placeholder which is shown while the page is in the `PageState::Error` state.
+    fn error_status(&self) -> Option<StatusDesc> {
+        None
+    }
+
+    /// Called when the user clicks the retry button of the error placeholder.
+    fn retry(&self) {}
+}
+
+/// A page which can be searched via the search bar of the leaflet_layout.
+pub trait Searchable {
+    /// Called whenever the search query changes. An empty query means the search was cleared.
+    ///
+    /// Returns whether the page contains results for the query. Matching pages are highlighted
+    /// in the sidebar.
+    fn search(&self, query: &str) -> bool;
+}
+
+pub type PageRestoreFn = Rc<dyn Fn(&HashMap<String, String>)>;
+pub type PageSearchFn = Rc<dyn Fn(&str) -> bool>;
+pub type PageRetryFn = Rc<dyn Fn()>;
+
+/// Describes a page for a `Layout`. Create it with `PageDesc::new` or `PageDesc::searchable`.
+#[non_exhaustive]
+pub struct PageDesc {
+    pub widget: gtk::Widget,
+    pub name: &'static str,
+    pub title_and_icon: Option<(String, String)>,
+    pub restore: PageRestoreFn,
+    pub section: Option<String>,
+    pub badge_number: u32,
+    pub needs_attention: bool,
+    pub header_widgets: Vec<HeaderWidget>,
+    pub search: Option<PageSearchFn>,
+    pub loading_status: Option<StatusDesc>,
+    pub empty_status: Option<StatusDesc>,
+    pub error_status: Option<StatusDesc>,
+    pub retry: PageRetryFn,
+}
+
+impl PageDesc {
+    pub fn new(page: impl Page + IsA<gtk::Widget>) -> Self {
+        let name = page.name();
+        let title_and_icon = page.title_and_icon();
+        let section = page.section();
+        let badge_number = page.badge_number();
+        let needs_attention = page.needs_attention();
+        let header_widgets = page.header_widgets();
+        let loading_status = page.loading_status();
+        let empty_status = page.empty_status();
+        let error_status = page.error_status();
+        let p = page.clone();
+        let retry_page = page.clone();
+        PageDesc {
+            widget: page.upcast(),
+            name,
+            title_and_icon,
+            restore: Rc::new(move |params| p.restore(params)),
+            section,
+            badge_number,
+            needs_attention,
+            header_widgets,
+            search: None,
+            loading_status,
+            empty_status,
+            error_status,
+            retry: Rc::new(move || retry_page.retry()),
+        }
+    }
+
+    /// Whether the page declares any placeholder and supports `PageState`s.
+    pub fn has_status(&self) -> bool {
+        self.loading_status.is_some() || self.empty_status.is_some() || self.error_status.is_some()
+    }
+
+    pub fn searchable(page: impl Page + Searchable + IsA<gtk::Widget>) -> Self {
+        let p = page.clone();
+        let mut desc = PageDesc::new(page);
+        desc.search = Some(Rc::new(move |query| p.search(query)));
+        desc
+    }
+}
+
+impl std::fmt::Debug for PageDesc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PageDesc")
+            .field("widget", &self.widget)
+            .field("name", &self.name)
+            .field("title_and_icon", &self.title_and_icon)
+            .field("section", &self.section)
+            .field("badge_number", &self.badge_number)
+            .field("needs_attention", &self.needs_attention)
+            .field("header_widgets", &self.header_widgets)
+            .field("loading_status", &self.loading_status)
+            .field("empty_status", &self.empty_status)
+            .field("error_status", &self.error_status)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A Header widget describes any widget that is supposed to be shown
+/// in the header bar of a layout.
+#[derive(Debug, Clone)]
+pub struct HeaderWidget {
+    pub(crate) widget: gtk::Widget,
+    pub(crate) alignment: HeaderAlignment,
+}
+
+/// Describes where to place a HeaderWidget in a gtk HeaderBar.
+#[derive(Debug, Clone)]
+pub(crate) enum HeaderAlignment {
+    Start,
+    End,
+}
+impl HeaderWidget {
+    pub fn start(widget: impl IsA<gtk::Widget>) -> Self {
+        HeaderWidget {
+            widget: widget.upcast(),
+            alignment: HeaderAlignment::Start,
+        }
+    }
+    pub fn end(widget: impl IsA<gtk::Widget>) -> Self {
+        HeaderWidget {
+            widget: widget.upcast(),
+            alignment: HeaderAlignment::End,
+        }
+    }
+
+    pub(crate) fn pack(&self, header: &adw::HeaderBar) {
+        match self.alignment {
+            HeaderAlignment::Start => header.pack_start(&self.widget),
+            HeaderAlignment::End => header.pack_end(&self.widget),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gdk4::subclass::prelude::ObjectSubclassIsExt;
+use gtk::prelude::*;
+use gtk_rust_app_derive::widget;
+use libadwaita as adw;
+
+/// Title, description and icon of a placeholder page.
+#[derive(Debug, Clone, Default)]
+pub struct StatusDesc {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub icon_name: Option<String>,
+}
+
+impl StatusDesc {
+    pub fn new(title: &str, description: &str, icon_name: &str) -> Self {
+        Self {
+            title: Some(title.into()),
+            description: Some(description.into()),
+            icon_name: Some(icon_name.into()),
+        }
+    }
+}
+
+/// The state of a page. Pages which are not in the `Content` state show a placeholder instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageState {
+    Content,
+    Loading,
+    Empty,
+    /// An error with a message that is shown below the error title.
+    Error(String),
+}
+
+/// Wraps a page and shows AdwStatusPage placeholders for the loading, empty and error state.
+///
+/// The error placeholder has a retry button which emits the `retry` signal.
+#[widget(extends gtk::Box)]
+#[template(file = "page_status.xml")]
+pub struct PageStatusContainer {
+    #[template_child]
+    pub stack: TemplateChild<gtk::Stack>,
+    #[template_child]
+    pub loading_page: TemplateChild<adw::StatusPage>,
+    #[template_child]
+    pub spinner: TemplateChild<gtk::Spinner>,
+    #[template_child]
+    pub empty_page: TemplateChild<adw::StatusPage>,
+    #[template_child]
+    pub error_page: TemplateChild<adw::StatusPage>,
+    #[template_child]
+    pub retry_button: TemplateChild<gtk::Button>,
+    #[signal_handler(retry_button clicked)]
+    pub on_retry_button_clicked: (),
+
+    #[signal]
+    retry: (),
+}
+
+impl PageStatusContainer {
+    pub fn new(
+        content: &impl IsA<gtk::Widget>,
+        loading: Option<StatusDesc>,
+        empty: Option<StatusDesc>,
+        error: Option<StatusDesc>,
+    ) -> Self {
+        let self_: Self = glib::Object::new(&[]).expect("Failed to create PageStatusContainer");
+        self_.stack().add_named(content, Some("content"));
+        self_.stack().set_visible_child_name("content");
+        apply_status(self_.loading_page(), loading);
+        apply_status(self_.empty_page(), empty);
+        apply_status(self_.error_page(), error);
+        self_
+    }
+
+    pub fn constructed(&self) {}
+
+    fn on_retry_button_clicked(&self, _: gtk::Button) {
+        self.emit_retry();
+    }
+
+    pub fn connect_retry(&self, f: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self._connect_retry(f)
+    }
+
+    pub fn set_state(&self, state: PageState) {
+        self.spinner().set_spinning(state == PageState::Loading);
+        match state {
+            PageState::Content => self.stack().set_visible_child_name("content"),
+            PageState::Loading => self.stack().set_visible_child_name("loading"),
+            PageState::Empty => self.stack().set_visible_child_name("empty"),
+            PageState::Error(message) => {
+                self.error_page().set_description(Some(&message));
+                self.stack().set_visible_child_name("error");
+            }
+        }
+    }
+}
+
+fn apply_status(status_page: &adw::StatusPage, desc: Option<StatusDesc>) {
+    if let Some(desc) = desc {
+        if let Some(title) = &desc.title {
+            status_page.set_title(title);
+        }
+        if desc.description.is_some() {
+            status_page.set_description(desc.description.as_deref());
+        }
+        if desc.icon_name.is_some() {
+            status_page.set_icon_name(desc.icon_name.as_deref());
+        }
+    }
+}
+
+/// Switch the state of the page which contains the given widget. Pages only support states
+/// if they declare at least one placeholder (see `Page::loading_status`).
+///
+/// Returns `false` if the widget is not part of such a page.
+pub fn set_page_state(widget: &impl IsA<gtk::Widget>, state: PageState) -> bool {
+    match widget
+        .ancestor(PageStatusContainer::static_type())
+        .and_then(|w| w.downcast::<PageStatusContainer>().ok())
+    {
+        Some(container) => {
+            container.set_state(state);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::collections::HashMap;
+
+/// A parsed deep link of the form `<scheme>://page/<page-name>?<key>=<value>&...`.
+///
+/// The page name is the name a `Page` returns from `Page::name()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub scheme: String,
+    pub page: String,
+    pub params: HashMap<String, String>,
+}
+
+impl Route {
+    /// Parse a deep link URI. Returns `None` if the URI does not point to a page.
+    ///
+    /// ```
+    /// use gtk_rust_app::widgets::Route;
+    ///
+    /// let route = Route::parse("myapp://page/settings?id=42").unwrap();
+    /// assert_eq!(route.scheme, "myapp");
+    /// assert_eq!(route.page, "settings");
+    /// assert_eq!(route.params.get("id").map(String::as_str), Some("42"));
+    /// ```
+    pub fn parse(uri: &str) -> Option<Route> {
+        let (scheme, rest) = uri.split_once("://")?;
+        let rest = rest.split('#').next().unwrap_or_default();
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        let page = path.strip_prefix("page/")?.trim_end_matches('/');
+        if page.is_empty() {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+        for pair in query.unwrap_or_default().split('&') {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.insert(unescape(key), unescape(value));
+        }
+
+        Some(Route {
+            scheme: scheme.to_ascii_lowercase(),
+            page: unescape(page),
+            params,
+        })
+    }
+}
+
+fn unescape(s: &str) -> String {
+    glib::uri_unescape_string(s, None::<&str>)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reports_the_lowercased_scheme() {
+        let route = Route::parse("OtherApp://page/settings").unwrap();
+        assert_eq!(route.scheme, "otherapp");
+        assert_ne!(route.scheme, "myapp");
+    }
+
+    #[test]
+    fn parse_rejects_uris_without_scheme_or_page() {
+        assert_eq!(Route::parse("page/settings"), None);
+        assert_eq!(Route::parse("myapp://settings"), None);
+    }
+
+    #[test]
+    fn parse_rejects_an_empty_path() {
+        assert_eq!(Route::parse("myapp://"), None);
+        assert_eq!(Route::parse("myapp://page/"), None);
+        assert_eq!(Route::parse("myapp://page/?id=1"), None);
+    }
+
+    #[test]
+    fn parse_ignores_a_trailing_slash() {
+        let route = Route::parse("myapp://page/settings/").unwrap();
+        assert_eq!(route.page, "settings");
+        assert!(route.params.is_empty());
+    }
+
+    #[test]
+    fn parse_unescapes_percent_encoded_segments() {
+        let route = Route::parse("myapp://page/my%20page?a%20b=c%26d").unwrap();
+        assert_eq!(route.page, "my page");
+        assert_eq!(route.params.get("a b").map(String::as_str), Some("c&d"));
+    }
+
+    #[test]
+    fn parse_reads_the_query_string() {
+        let route = Route::parse("myapp://page/todo?id=42&edit&&empty=#fragment").unwrap();
+        assert_eq!(route.page, "todo");
+        assert_eq!(route.params.len(), 3);
+        assert_eq!(route.params.get("id").map(String::as_str), Some("42"));
+        assert_eq!(route.params.get("edit").map(String::as_str), Some(""));
+        assert_eq!(route.params.get("empty").map(String::as_str), Some(""));
+    }
+}
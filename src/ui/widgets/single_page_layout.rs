@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use gdk4::subclass::prelude::ObjectSubclassIsExt;
+use gtk::prelude::*;
+use gtk_rust_app_derive::widget;
+use libadwaita as adw;
+
+use super::layout::page_widget;
+use super::{HeaderWidget, Layout, LayoutBuilder, PageDesc, ToastBuilder, ToastQueue};
+
+/// A minimal layout with a header bar, a toast overlay and one visible page.
+///
+/// ```txt
+/// ┌─────────────────────────────┐
+/// │           Title         -xo │
+/// ├─────────────────────────────┤
+/// │                             │
+/// │                             │
+/// │                             │
+/// └─────────────────────────────┘
+/// ```
+///
+/// The first page is visible initially. Other pages are only shown via `show_page`.
+#[widget(extends gtk::Box)]
+#[template(file = "single_page_layout.xml")]
+pub struct SinglePageLayout {
+    #[template_child]
+    pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+    #[template_child]
+    pub header: TemplateChild<adw::HeaderBar>,
+    #[template_child]
+    pub window_title: TemplateChild<adw::WindowTitle>,
+    #[template_child]
+    pub stack: TemplateChild<gtk::Stack>,
+
+    pub titles: RefCell<HashMap<String, String>>,
+    pub page_header_widgets: RefCell<HashMap<String, Vec<HeaderWidget>>>,
+    pub toasts: ToastQueue,
+}
+
+impl SinglePageLayout {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create SinglePageLayout")
+    }
+
+    pub fn builder() -> LayoutBuilder<Self> {
+        LayoutBuilder::new(Self::new())
+    }
+
+    pub fn constructed(&self) {
+        let s = self;
+        self.imp()
+            .stack
+            .connect_visible_child_name_notify(glib::clone!(@weak s => move |_| {
+                s.on_visible_page_changed();
+            }));
+    }
+
+    pub fn get_toast_overlay(&self) -> &adw::ToastOverlay {
+        self.toast_overlay()
+    }
+    pub fn get_header(&self) -> &adw::HeaderBar {
+        self.header()
+    }
+    pub fn get_stack(&self) -> &gtk::Stack {
+        self.stack()
+    }
+
+    fn on_visible_page_changed(&self) {
+        let name = self.stack().visible_child_name().map(|n| n.to_string());
+        let title = name
+            .as_ref()
+            .and_then(|n| self.imp().titles.borrow().get(n).cloned())
+            .unwrap_or_default();
+        self.window_title().set_title(&title);
+        for (page_name, header_widgets) in self.imp().page_header_widgets.borrow().iter() {
+            let visible = Some(page_name) == name.as_ref();
+            for hw in header_widgets {
+                hw.widget.set_visible(visible);
+            }
+        }
+    }
+}
+
+impl Layout for SinglePageLayout {
+    fn push_page(&self, page: PageDesc) {
+        if let Some((title, _)) = &page.title_and_icon {
+            self.imp()
+                .titles
+                .borrow_mut()
+                .insert(page.name.to_string(), title.clone());
+        }
+        for hw in &page.header_widgets {
+            hw.pack(self.header());
+        }
+        self.imp()
+            .page_header_widgets
+            .borrow_mut()
+            .insert(page.name.to_string(), page.header_widgets);
+        self.stack().add_named(&page_widget(&page), Some(page.name));
+        self.on_visible_page_changed();
+    }
+
+    fn add_header_widget(&self, header_widget: &HeaderWidget) {
+        header_widget.pack(self.header());
+    }
+
+    fn show_page(&self, name: &str) {
+        self.stack().set_visible_child_name(name);
+    }
+
+    fn toast(&self, toast: ToastBuilder) -> adw::Toast {
+        self.imp().toasts.show(self.toast_overlay(), &toast)
+    }
+}
+
+impl Default for SinglePageLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,164 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use gdk4::subclass::prelude::ObjectSubclassIsExt;
+use gtk::prelude::*;
+use gtk_rust_app_derive::widget;
+use libadwaita as adw;
+
+use super::layout::page_widget;
+use super::{HeaderWidget, Layout, LayoutBuilder, PageDesc, ToastBuilder, ToastQueue};
+
+/// A tabbed document layout based on AdwTabView and AdwTabBar.
+///
+/// ```txt
+/// ┌─────────────────────────────┐
+/// │                         -xo │
+/// ├─────────┬─────────┬─────────┤
+/// │ Doc1  x │ Doc2  x │ Doc3  x │
+/// ├─────────┴─────────┴─────────┤
+/// │                             │
+/// │                             │
+/// │                             │
+/// └─────────────────────────────┘
+/// ```
+///
+/// Every page becomes a tab. Closed tabs are removed from the layout.
+#[widget(extends gtk::Box)]
+#[template(file = "tab_layout.xml")]
+pub struct TabLayout {
+    #[template_child]
+    pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+    #[template_child]
+    pub header: TemplateChild<adw::HeaderBar>,
+    #[template_child]
+    pub tab_bar: TemplateChild<adw::TabBar>,
+    #[template_child]
+    pub tab_view: TemplateChild<adw::TabView>,
+
+    pub pages: RefCell<HashMap<String, adw::TabPage>>,
+    pub page_header_widgets: RefCell<HashMap<String, Vec<HeaderWidget>>>,
+    pub toasts: ToastQueue,
+}
+
+impl TabLayout {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create TabLayout")
+    }
+
+    pub fn builder() -> LayoutBuilder<Self> {
+        LayoutBuilder::new(Self::new())
+    }
+
+    pub fn constructed(&self) {
+        let s = self;
+        self.imp()
+            .tab_view
+            .connect_selected_page_notify(glib::clone!(@weak s => move |_| {
+                s.update_page_header_widgets();
+            }));
+        self.imp()
+            .tab_view
+            .connect_page_detached(glib::clone!(@weak s => move |_, tab_page, _| {
+                s.remove_page(tab_page);
+            }));
+    }
+
+    pub fn get_toast_overlay(&self) -> &adw::ToastOverlay {
+        self.toast_overlay()
+    }
+    pub fn get_header(&self) -> &adw::HeaderBar {
+        self.header()
+    }
+    pub fn get_tab_bar(&self) -> &adw::TabBar {
+        self.tab_bar()
+    }
+    pub fn get_tab_view(&self) -> &adw::TabView {
+        self.tab_view()
+    }
+
+    /// Close the tab of the page with the given name.
+    pub fn close_page(&self, name: &str) {
+        let tab_page = self.imp().pages.borrow().get(name).cloned();
+        if let Some(tab_page) = tab_page {
+            self.tab_view().close_page(&tab_page);
+        }
+    }
+
+    fn remove_page(&self, tab_page: &adw::TabPage) {
+        let name = self
+            .imp()
+            .pages
+            .borrow()
+            .iter()
+            .find(|(_, p)| *p == tab_page)
+            .map(|(name, _)| name.clone());
+        let name = match name {
+            Some(name) => name,
+            None => return,
+        };
+        self.imp().pages.borrow_mut().remove(&name);
+        if let Some(header_widgets) = self.imp().page_header_widgets.borrow_mut().remove(&name) {
+            for hw in header_widgets {
+                self.header().remove(&hw.widget);
+            }
+        }
+    }
+
+    fn update_page_header_widgets(&self) {
+        let selected = self.tab_view().selected_page();
+        let pages = self.imp().pages.borrow();
+        for (name, header_widgets) in self.imp().page_header_widgets.borrow().iter() {
+            let visible = selected.is_some() && pages.get(name) == selected.as_ref();
+            for hw in header_widgets {
+                hw.widget.set_visible(visible);
+            }
+        }
+    }
+}
+
+impl Layout for TabLayout {
+    fn push_page(&self, page: PageDesc) {
+        let tab_page = self.tab_view().append(&page_widget(&page));
+        if let Some((title, icon)) = &page.title_and_icon {
+            tab_page.set_title(title);
+            tab_page.set_icon(Some(&gdk4::gio::ThemedIcon::new(icon)));
+        }
+        tab_page.set_needs_attention(page.needs_attention);
+        for hw in &page.header_widgets {
+            hw.pack(self.header());
+        }
+        self.imp()
+            .page_header_widgets
+            .borrow_mut()
+            .insert(page.name.to_string(), page.header_widgets);
+        self.imp()
+            .pages
+            .borrow_mut()
+            .insert(page.name.to_string(), tab_page);
+        self.update_page_header_widgets();
+    }
+
+    fn add_header_widget(&self, header_widget: &HeaderWidget) {
+        header_widget.pack(self.header());
+    }
+
+    fn show_page(&self, name: &str) {
+        let tab_page = self.imp().pages.borrow().get(name).cloned();
+        if let Some(tab_page) = tab_page {
+            self.tab_view().set_selected_page(&tab_page);
+        }
+    }
+
+    fn toast(&self, toast: ToastBuilder) -> adw::Toast {
+        self.imp().toasts.show(self.toast_overlay(), &toast)
+    }
+}
+
+impl Default for TabLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
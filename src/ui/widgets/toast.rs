@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use glib::{ToVariant, Variant};
+use libadwaita as adw;
+
+/// Describes a toast with an optional action button, timeout, priority and key.
+///
+/// Showing a toast with a key dismisses earlier toasts with the same key.
+///
+/// ```no_run
+/// # fn f(layout: &gtk_rust_app::widgets::LeafletLayout) {
+/// use gtk_rust_app::widgets::ToastBuilder;
+/// use glib::ToVariant;
+///
+/// layout.toast(
+///     ToastBuilder::new("Item deleted")
+///         .button("Undo", "app.restore-item")
+///         .action_target(&"item-42".to_variant())
+///         .key("item-deleted"),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ToastBuilder {
+    title: String,
+    button: Option<(String, String)>,
+    action_target: Option<Variant>,
+    timeout: Option<u32>,
+    priority: adw::ToastPriority,
+    key: Option<String>,
+}
+
+impl ToastBuilder {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.into(),
+            button: None,
+            action_target: None,
+            timeout: None,
+            priority: adw::ToastPriority::Normal,
+            key: None,
+        }
+    }
+
+    /// Show a button which activates the given detailed action name, e.g. `app.undo`.
+    pub fn button(mut self, label: &str, action_name: &str) -> Self {
+        self.button = Some((label.into(), action_name.into()));
+        self
+    }
+
+    /// Show an "Undo" button which activates `app.undo` (see `undoable` actions in the App.toml).
+    pub fn undo_button(self) -> Self {
+        self.button(&gettextrs::gettext("Undo"), "app.undo")
+    }
+
+    pub fn action_target(mut self, target: &Variant) -> Self {
+        self.action_target = Some(target.clone());
+        self
+    }
+
+    /// Timeout in seconds. `0` keeps the toast until it is dismissed.
+    pub fn timeout(mut self, timeout: u32) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn priority(mut self, priority: adw::ToastPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    pub fn build(&self) -> adw::Toast {
+        let toast = adw::Toast::new(&self.title);
+        if let Some((label, action_name)) = &self.button {
+            toast.set_button_label(Some(label));
+            toast.set_action_name(Some(action_name));
+        }
+        if let Some(target) = &self.action_target {
+            toast.set_action_target_value(Some(target));
+        }
+        if let Some(timeout) = self.timeout {
+            toast.set_timeout(timeout);
+        }
+        toast.set_priority(self.priority);
+        toast
+    }
+
+    /// Serialize the toast as `a{sv}` dictionary to pass it to the `app.show-toast` action.
+    ///
+    /// Keys: `title`, `button-label`, `action-name`, `action-target`, `timeout`, `priority` (`"high"` or `"normal"`) and `key`.
+    pub fn to_variant(&self) -> Variant {
+        let dict = glib::VariantDict::new(None);
+        dict.insert_value("title", &self.title.to_variant());
+        if let Some((label, action_name)) = &self.button {
+            dict.insert_value("button-label", &label.to_variant());
+            dict.insert_value("action-name", &action_name.to_variant());
+        }
+        if let Some(target) = &self.action_target {
+            dict.insert_value("action-target", &Variant::from_variant(target));
+        }
+        if let Some(timeout) = self.timeout {
+            dict.insert_value("timeout", &timeout.to_variant());
+        }
+        if self.priority == adw::ToastPriority::High {
+            dict.insert_value("priority", &"high".to_variant());
+        }
+        if let Some(key) = &self.key {
+            dict.insert_value("key", &key.to_variant());
+        }
+        dict.end()
+    }
+
+    /// Parse a toast from the `a{sv}` dictionary created by `to_variant`.
+    pub fn from_variant(variant: &Variant) -> Option<Self> {
+        let dict = glib::VariantDict::new(Some(variant));
+        let string = |key: &str| {
+            dict.lookup_value(key, Some(glib::VariantTy::STRING))
+                .and_then(|v| v.get::<String>())
+        };
+
+        let mut toast = ToastBuilder::new(&string("title")?);
+        if let (Some(label), Some(action_name)) = (string("button-label"), string("action-name")) {
+            toast = toast.button(&label, &action_name);
+        }
+        if let Some(target) = dict
+            .lookup_value("action-target", Some(glib::VariantTy::VARIANT))
+            .and_then(|v| v.as_variant())
+        {
+            toast = toast.action_target(&target);
+        }
+        if let Some(timeout) = dict
+            .lookup_value("timeout", Some(glib::VariantTy::UINT32))
+            .and_then(|v| v.get::<u32>())
+        {
+            toast = toast.timeout(timeout);
+        }
+        if string("priority").as_deref() == Some("high") {
+            toast = toast.priority(adw::ToastPriority::High);
+        }
+        if let Some(key) = string("key") {
+            toast = toast.key(&key);
+        }
+        Some(toast)
+    }
+}
+
+/// Keeps track of keyed toasts of a toast overlay.
+#[derive(Debug, Default)]
+pub struct ToastQueue {
+    keyed: Rc<RefCell<HashMap<String, adw::Toast>>>,
+}
+
+impl ToastQueue {
+    pub fn show(&self, overlay: &adw::ToastOverlay, builder: &ToastBuilder) -> adw::Toast {
+        let toast = builder.build();
+        if let Some(key) = &builder.key {
+            self.dismiss(key);
+            self.keyed.borrow_mut().insert(key.clone(), toast.clone());
+            let keyed = self.keyed.clone();
+            let key = key.clone();
+            toast.connect_dismissed(move |toast| {
+                let mut keyed = keyed.borrow_mut();
+                if keyed.get(&key) == Some(toast) {
+                    keyed.remove(&key);
+                }
+            });
+        }
+        overlay.add_toast(&toast);
+        toast
+    }
+
+    /// Dismiss the toast that was shown with the given key.
+    pub fn dismiss(&self, key: &str) {
+        let toast = self.keyed.borrow_mut().remove(key);
+        if let Some(toast) = toast {
+            toast.dismiss();
+        }
+    }
+}
@@ -0,0 +1,310 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Undo and redo actions which are marked with `undoable = true` in the App.toml.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use gdk4::gio::SimpleAction;
+use gdk4::prelude::*;
+use gra::ProjectDescriptor;
+use gtk::prelude::GtkApplicationExt;
+
+use crate::actions::ActionExtras;
+
+/// The number of undo steps which are kept.
+const UNDO_DEPTH: usize = 100;
+
+/// The gstore action which restores the state before the last undoable action.
+pub(crate) const UNDO_ACTION: &str = "undo";
+/// The gstore action which restores the state after the last undone action.
+pub(crate) const REDO_ACTION: &str = "redo";
+
+struct UndoStacks<S> {
+    undo: Vec<S>,
+    redo: Vec<S>,
+    /// The state before the action which is currently reduced.
+    before: Option<S>,
+}
+
+impl<S: PartialEq> UndoStacks<S> {
+    fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            before: None,
+        }
+    }
+
+    /// Push the state before an undoable action. Actions which did not change the state are not
+    /// recorded.
+    fn record(&mut self, before: S, after: &S) {
+        if &before == after {
+            return;
+        }
+        self.undo.push(before);
+        if self.undo.len() > UNDO_DEPTH {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Return the state to restore and keep `current` for redo.
+    fn undo(&mut self, current: S) -> Option<S> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// Return the state to restore and keep `current` for undo.
+    fn redo(&mut self, current: S) -> Option<S> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+struct UndoMiddleware<S> {
+    stacks: Rc<RefCell<UndoStacks<S>>>,
+    undoable: HashSet<String>,
+    undo_action: SimpleAction,
+    redo_action: SimpleAction,
+}
+
+impl<S: PartialEq> UndoMiddleware<S> {
+    fn update_sensitivity(&self) {
+        let stacks = self.stacks.borrow();
+        self.undo_action.set_enabled(!stacks.undo.is_empty());
+        self.redo_action.set_enabled(!stacks.redo.is_empty());
+    }
+}
+
+impl<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static> gstore::Middleware<S>
+    for UndoMiddleware<S>
+{
+    fn pre_reduce(&self, a: &gstore::Action, s: &S) {
+        let mut stacks = self.stacks.borrow_mut();
+        let restored = match a.name() {
+            crate::store::REPLACE_STATE_ACTION => return,
+            UNDO_ACTION => stacks.undo(s.clone()),
+            REDO_ACTION => stacks.redo(s.clone()),
+            _ => {
+                stacks.before = Some(s.clone());
+                return;
+            }
+        };
+        // The replaceable reducer applies the restored state for this action.
+        if let Some(state) = restored {
+            crate::store::set_pending_state(state);
+        }
+    }
+
+    fn post_reduce(&self, a: &gstore::Action, s: &S) {
+        {
+            let mut stacks = self.stacks.borrow_mut();
+            let before = stacks.before.take();
+            match a.name() {
+                crate::store::REPLACE_STATE_ACTION | UNDO_ACTION | REDO_ACTION => {}
+                name if self.undoable.contains(name) => {
+                    if let Some(before) = before {
+                        stacks.record(before, s);
+                    }
+                }
+                // Snapshots contain the whole state. Undoing past an action which is not
+                // undoable would revert its changes as well.
+                _ => {
+                    if before.as_ref() != Some(s) {
+                        stacks.clear();
+                    }
+                }
+            }
+        }
+        self.update_sensitivity();
+    }
+}
+
+/// Add the `app.undo` and `app.redo` actions (`<primary>Z` and `<primary><shift>Z`) if any action
+/// of the App.toml is marked with `undoable = true`.
+///
+/// Actions which are not undoable and change the state clear the undo and redo history.
+/// Undo and redo are dispatched as the gstore actions `undo` and `redo`.
+/// The store reducer has to be wrapped by `store::replaceable_reducer`.
+pub(crate) fn add_undo<S: std::fmt::Debug + Clone + Default + PartialEq + Eq + 'static>(
+    application: &gtk::Application,
+    project_descriptor: &ProjectDescriptor,
+    extras: &HashMap<String, ActionExtras>,
+    store: &mut gstore::Store<S>,
+) {
+    let actions = match &project_descriptor.actions {
+        Some(actions) => actions,
+        None => return,
+    };
+    let undoable: HashSet<String> = actions
+        .keys()
+        .filter(|name| extras.get(*name).and_then(|e| e.undoable).unwrap_or(false))
+        .cloned()
+        .collect();
+    if undoable.is_empty() {
+        return;
+    }
+    if actions.contains_key("undo") || actions.contains_key("redo") {
+        warn!("The App.toml declares 'undo' or 'redo' actions. Undoable actions are ignored.");
+        return;
+    }
+    if !crate::store::has_replaceable_reducer() {
+        warn!(
+            "Undoable actions require the store reducer to be wrapped by \
+            gtk_rust_app::store::replaceable_reducer. Undo and redo are disabled."
+        );
+        return;
+    }
+
+    let stacks = Rc::new(RefCell::new(UndoStacks::new()));
+    let undo_action = SimpleAction::new(UNDO_ACTION, None);
+    let redo_action = SimpleAction::new(REDO_ACTION, None);
+    undo_action.set_enabled(false);
+    redo_action.set_enabled(false);
+
+    // Undo and redo go through the store to show up in the debug window, recorded sessions
+    // and crash reports.
+    let delegate = store.delegate();
+    undo_action.connect_activate(move |_, _| {
+        crate::store::dispatch(&delegate, UNDO_ACTION, None);
+    });
+    let delegate = store.delegate();
+    redo_action.connect_activate(move |_, _| {
+        crate::store::dispatch(&delegate, REDO_ACTION, None);
+    });
+
+    store.append_middleware(Box::new(UndoMiddleware {
+        stacks,
+        undoable,
+        undo_action: undo_action.clone(),
+        redo_action: redo_action.clone(),
+    }));
+
+    application.add_action(&undo_action);
+    application.add_action(&redo_action);
+    application.set_accels_for_action("app.undo", &["<primary>Z"]);
+    application.set_accels_for_action("app.redo", &["<primary><shift>Z"]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_restore_states() {
+        let mut stacks = UndoStacks::new();
+        stacks.record(0, &1);
+        stacks.record(1, &2);
+        assert_eq!(stacks.undo(2), Some(1));
+        assert_eq!(stacks.undo(1), Some(0));
+        assert_eq!(stacks.redo(0), Some(1));
+        assert_eq!(stacks.undo, vec![0]);
+        assert_eq!(stacks.redo, vec![2]);
+    }
+
+    #[test]
+    fn undo_on_empty_stack_is_noop() {
+        let mut stacks = UndoStacks::new();
+        assert_eq!(stacks.undo(0), None);
+        assert_eq!(stacks.redo(0), None);
+        assert!(stacks.undo.is_empty());
+        assert!(stacks.redo.is_empty());
+    }
+
+    #[test]
+    fn new_undoable_action_clears_redo() {
+        let mut stacks = UndoStacks::new();
+        stacks.record(0, &1);
+        stacks.undo(1);
+        assert_eq!(stacks.redo, vec![1]);
+        stacks.record(0, &2);
+        assert_eq!(stacks.undo, vec![0]);
+        assert!(stacks.redo.is_empty());
+    }
+
+    #[test]
+    fn unchanged_state_is_not_recorded() {
+        let mut stacks = UndoStacks::new();
+        stacks.record(0, &1);
+        stacks.undo(1);
+        stacks.record(0, &0);
+        assert!(stacks.undo.is_empty());
+        assert_eq!(stacks.redo, vec![1]);
+    }
+
+    #[test]
+    fn clear_removes_the_history() {
+        let mut stacks = UndoStacks::new();
+        stacks.record(0, &1);
+        stacks.record(1, &2);
+        stacks.undo(2);
+        stacks.clear();
+        assert!(stacks.undo.is_empty());
+        assert!(stacks.redo.is_empty());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut stacks = UndoStacks::new();
+        for i in 0..UNDO_DEPTH + 10 {
+            stacks.record(i, &(i + 1));
+        }
+        assert_eq!(stacks.undo.len(), UNDO_DEPTH);
+        assert_eq!(stacks.undo.first(), Some(&10));
+        assert_eq!(stacks.undo.last(), Some(&(UNDO_DEPTH + 9)));
+    }
+
+    fn middleware() -> UndoMiddleware<i32> {
+        UndoMiddleware {
+            stacks: Rc::new(RefCell::new(UndoStacks::new())),
+            undoable: ["add".to_string()].into_iter().collect(),
+            undo_action: SimpleAction::new(UNDO_ACTION, None),
+            redo_action: SimpleAction::new(REDO_ACTION, None),
+        }
+    }
+
+    fn reduce(m: &UndoMiddleware<i32>, name: &str, before: i32, after: i32) {
+        use gstore::Middleware;
+        let action = gstore::Action::new(name, None);
+        m.pre_reduce(&action, &before);
+        m.post_reduce(&action, &after);
+    }
+
+    #[test]
+    fn non_undoable_change_clears_the_history() {
+        let m = middleware();
+        reduce(&m, "add", 0, 1);
+        reduce(&m, "add", 1, 2);
+        reduce(&m, UNDO_ACTION, 2, 1);
+        assert!(m.undo_action.is_enabled());
+        assert!(m.redo_action.is_enabled());
+
+        reduce(&m, "select", 1, 1);
+        assert_eq!(m.stacks.borrow().undo, vec![0]);
+
+        reduce(&m, "reset", 1, 0);
+        assert!(m.stacks.borrow().undo.is_empty());
+        assert!(m.stacks.borrow().redo.is_empty());
+        assert!(!m.undo_action.is_enabled());
+        assert!(!m.redo_action.is_enabled());
+    }
+
+    #[test]
+    fn undoable_action_without_change_keeps_redo() {
+        let m = middleware();
+        reduce(&m, "add", 0, 1);
+        reduce(&m, UNDO_ACTION, 1, 0);
+        reduce(&m, "add", 0, 0);
+        assert!(m.stacks.borrow().undo.is_empty());
+        assert_eq!(m.stacks.borrow().redo, vec![1]);
+    }
+}